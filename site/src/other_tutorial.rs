@@ -253,6 +253,24 @@ pub fn Documentation() -> impl IntoView {
         <p>"Calling an "<code>"# External!"</code>" function that hasn't been bound will throw an error."</p>
         <Editor example="F ← |2 # External!\nF 1 2"/> // Should fail
         <p>"To be compatible with "<code>"# External!"</code>", Rust functions should be bound via "<a href="https://docs.rs/uiua/latest/uiua/struct.Compiler.html#method.create_bind_function"><code>"Compiler::create_bind_function"</code></a>"."</p>
+
+        <Hd id="eq"><code>"# Eq!"</code></Hd>
+
+        <p>"The "<code>"# Eq!"</code>" semantic comment can be placed on the line above a named "<A href="/tutorial/datadefs">"data definition"</A>" with fields. It generates an "<code>"Eq"</code>" function that checks whether two instances are equal."</p>
+        <Editor example="# Eq!\n~Pt {X Y}\nPt~Eq Pt 1 2 Pt 1 2\nPt~Eq Pt 1 2 Pt 1 3"/>
+        <p><code>"Eq"</code>" returns "<code>"0"</code>" for values that do not have the shape of an instance. Instances of "<code>"# Eq!"</code>" definitions are labeled with the definition's name, so instances of different definitions are never equal, even if they have the same fields. Different variants are not equal either."</p>
+        <Editor example="# Eq!\n~Pt {X Y}\n# Eq!\n~Vc {X Y}\nPt~Eq Pt 1 2 [1 2]\nPt~Eq Pt 1 2 Vc 1 2"/>
+        <p>"Using "<code>"# Eq!"</code>" anywhere other than above a data definition is an error."</p>
+        <Editor example="# Eq!\nF ← +1"/> // Should fail
+
+        <Hd id="match"><code>"Match!"</code></Hd>
+
+        <p>"While not a semantic comment, the built-in "<code>"Match!"</code>" macro is another way to derive code from "<A href="/tutorial/datadefs">"data definitions"</A>". It calls a different function depending on which variant a value is."</p>
+        <p>"Each branch starts with the name of a variant. The rest of the branch is called with that variant's fields."</p>
+        <Editor example="┌─╴Shape\n  |Circle {Radius}\n  |Rectangle {Width Height}\n  |Point\n  Area ← Match!(\n    Circle ×π×.\n  | Rectangle ×\n  | Point 0\n  )\n└─╴\nShape!(\n  Area Circle 4\n  Area Rectangle 3 5\n  Area Point\n)"/>
+        <p>"Matching a variant that has no branch is an error."</p>
+        <Editor example="┌─╴Shape\n  |Circle {Radius}\n  |Point\n  Area ← Match!(Circle ×π×.)\n└─╴\nShape~Area Shape~Point"/> // Should fail
+        <p>"If a binding named "<code>"Match!"</code>" is in scope, it is used instead of the built-in macro."</p>
     }
 }
 
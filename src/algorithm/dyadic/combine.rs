@@ -5,7 +5,9 @@ use std::{cmp::Ordering, iter::once};
 use ecow::EcoVec;
 
 use crate::{
-    algorithm::{max_shape, validate_size_impl, validate_size_of, FillContext, Indexable},
+    algorithm::{
+        max_shape, validate_size, validate_size_impl, validate_size_of, FillContext, Indexable,
+    },
     cowslice::cowslice,
     fill::FillValue,
     val_as_arr, Array, ArrayValue, Boxed, Complex, FormatShape, Primitive, Shape, Uiua, UiuaResult,
@@ -99,6 +101,65 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    /// Pad the array with a scalar fill value so it matches the given shape
+    ///
+    /// Missing leading axes are added. Errors if any axis is longer than the target.
+    pub fn pad_to_shape(self, target: &Shape, fill: &Value, env: &Uiua) -> UiuaResult<Self> {
+        if fill.rank() != 0 {
+            return Err(env.error(format!(
+                "Pad fill must be a scalar, but it has shape {}",
+                fill.shape()
+            )));
+        }
+        if self.rank() > target.len()
+            || (self.shape().iter().rev())
+                .zip(target.iter().rev())
+                .any(|(a, b)| a > b)
+        {
+            return Err(env.error(format!(
+                "Cannot pad array of shape {} to shape {}",
+                self.shape(),
+                target
+            )));
+        }
+        fn pad<T: ArrayValue>(
+            mut arr: Array<T>,
+            target: &Shape,
+            fill: T,
+            env: &Uiua,
+        ) -> UiuaResult<Value>
+        where
+            Value: From<Array<T>>,
+        {
+            validate_size::<T>(target.iter().copied(), env)?;
+            arr.fill_to_shape(target, FillValue::new(fill, None));
+            Ok(arr.into())
+        }
+        match (self, fill) {
+            (Value::Num(arr), Value::Num(f)) => pad(arr, target, f.data[0], env),
+            (Value::Num(arr), Value::Byte(f)) => pad(arr, target, f.data[0] as f64, env),
+            (Value::Byte(arr), Value::Byte(f)) => pad(arr, target, f.data[0], env),
+            (Value::Byte(arr), Value::Num(f)) => pad(arr.convert(), target, f.data[0], env),
+            (Value::Complex(arr), Value::Complex(f)) => pad(arr, target, f.data[0], env),
+            (Value::Complex(arr), Value::Num(f)) => {
+                pad(arr, target, Complex::new(f.data[0], 0.0), env)
+            }
+            (Value::Complex(arr), Value::Byte(f)) => {
+                pad(arr, target, Complex::new(f.data[0] as f64, 0.0), env)
+            }
+            (Value::Char(arr), Value::Char(f)) => pad(arr, target, f.data[0], env),
+            (Value::Box(arr), Value::Box(f)) => pad(arr, target, f.data[0].clone(), env),
+            (Value::Box(arr), f) => pad(arr, target, Boxed(f.clone()), env),
+            (val, f) => Err(env.error(format!(
+                "Cannot pad {} array with {}",
+                val.type_name(),
+                f.type_name_plural()
+            ))),
+        }
+    }
+}
+
 impl Value {
     /// `join` the array with another
     ///
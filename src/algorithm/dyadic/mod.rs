@@ -1184,6 +1184,72 @@ impl Value {
             ))),
         }
     }
+    /// Multiply two matrices
+    ///
+    /// Both values must be rank 2 numeric arrays,
+    /// and the number of columns of `a` must match the number of rows of `b`.
+    pub fn matmul(a: Value, b: Value, env: &Uiua) -> UiuaResult<Value> {
+        let matrix = |val: Value| match val {
+            Value::Num(arr) => Ok(arr),
+            Value::Byte(arr) => Ok(arr.convert()),
+            val => Err(env.error(format!("Cannot matrix multiply {} array", val.type_name()))),
+        };
+        let (a, b) = (matrix(a)?, matrix(b)?);
+        if a.rank() != 2 || b.rank() != 2 {
+            return Err(env.error(format!(
+                "Matrix multiplication requires arrays of rank 2, \
+                but their shapes are {} and {}",
+                a.shape, b.shape
+            )));
+        }
+        let [m, k] = [a.shape[0], a.shape[1]];
+        let n = b.shape[1];
+        if b.shape[0] != k {
+            return Err(env.error(format!(
+                "Cannot matrix multiply arrays of shape {} and {}",
+                a.shape, b.shape
+            )));
+        }
+        validate_size::<f64>([m, n], env)?;
+        // Small matrices are padded to 4×4 so that the loops have constant bounds
+        if m <= 4 && k <= 4 && n <= 4 {
+            let mut pa = [[0.0; 4]; 4];
+            let mut pb = [[0.0; 4]; 4];
+            for (dst, src) in pa.iter_mut().zip(a.data.chunks_exact(k.max(1))) {
+                dst[..k].copy_from_slice(src);
+            }
+            for (dst, src) in pb.iter_mut().zip(b.data.chunks_exact(n.max(1))) {
+                dst[..n].copy_from_slice(src);
+            }
+            let mut prod = [[0.0; 4]; 4];
+            for (i, row) in prod.iter_mut().enumerate() {
+                for (j, res) in row.iter_mut().enumerate() {
+                    *res = (0..4).fold(0.0, |acc, p| acc + pa[i][p] * pb[p][j]);
+                }
+            }
+            let data: EcoVec<f64> = (prod[..m].iter())
+                .flat_map(|row| row[..n].iter().copied())
+                .collect();
+            return Ok(Array::new([m, n], data).into());
+        }
+        let mut data = eco_vec![0.0; m * n];
+        if n > 0 {
+            let inner = |(a_row, res_row): (&[f64], &mut [f64])| {
+                for (&a, b_row) in a_row.iter().zip(b.data.chunks_exact(n)) {
+                    for (res, &b) in res_row.iter_mut().zip(b_row) {
+                        *res += a * b;
+                    }
+                }
+            };
+            let iter = (a.data.chunks_exact(k.max(1))).zip(data.make_mut().chunks_exact_mut(n));
+            par_if!(
+                m * n * k > 100_000,
+                iter.par_bridge().for_each(inner),
+                iter.for_each(inner)
+            )
+        }
+        Ok(Array::new([m, n], data).into())
+    }
 }
 
 impl Array<f64> {
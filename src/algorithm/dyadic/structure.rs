@@ -170,6 +170,74 @@ impl Value {
             },
         )
     }
+    /// Add values into an array at the given indices
+    ///
+    /// `indices` is interpreted the same way as the index of [`Value::pick`].
+    /// `values` must have the shape of the array that picking would produce.
+    /// Values at repeated indices accumulate.
+    pub fn scatter_add(
+        target: Value,
+        indices: &Value,
+        values: &Value,
+        env: &Uiua,
+    ) -> UiuaResult<Value> {
+        let (idx_shape, index_data) = indices.as_shaped_indices(false, env)?;
+        let (outer_shape, index_len) = match idx_shape {
+            [] | [_] => (&[][..], index_data.len()),
+            [outer @ .., last] => (outer, *last),
+        };
+        let mut target = match target {
+            Value::Num(arr) => arr,
+            Value::Byte(arr) => arr.convert(),
+            val => return Err(env.error(format!("Cannot scatter into {} array", val.type_name()))),
+        };
+        let value_data: Cow<[f64]> = match values {
+            Value::Num(arr) => Cow::Borrowed(&arr.data),
+            Value::Byte(arr) => arr.data.iter().map(|&b| b as f64).collect(),
+            val => return Err(env.error(format!("Cannot scatter {} array", val.type_name()))),
+        };
+        if index_len > target.rank() {
+            return Err(env.error(format!(
+                "Cannot scatter into rank {} array with index of length {index_len}",
+                target.rank()
+            )));
+        }
+        let mut expected_shape = Shape::from(outer_shape);
+        expected_shape.extend_from_slice(&target.shape[index_len..]);
+        if *values.shape() != expected_shape {
+            return Err(env.error(format!(
+                "Cannot scatter values with shape {} \
+                using indices with shape {} into array with shape {}",
+                values.shape(),
+                FormatShape(idx_shape),
+                target.shape
+            )));
+        }
+        let cell_len: usize = target.shape[index_len..].iter().product();
+        let count: usize = outer_shape.iter().product();
+        let data = target.data.as_mut_slice();
+        for k in 0..count {
+            let index = &index_data[k * index_len..][..index_len];
+            let mut pos = 0;
+            for (d, (&s, &i)) in target.shape.iter().zip(index).enumerate() {
+                if i >= s as isize || i < -(s as isize) {
+                    return Err(env.error(format!(
+                        "Index {i} is out of bounds of length {s} \
+                        (dimension {d}) in shape {}",
+                        target.shape
+                    )));
+                }
+                pos = pos * s + normalize_index(i, s);
+            }
+            let start = pos * cell_len;
+            let cell = &value_data[k * cell_len..][..cell_len];
+            for (a, b) in data[start..][..cell_len].iter_mut().zip(cell) {
+                *a += *b;
+            }
+        }
+        target.reset_meta_flags();
+        Ok(target.into())
+    }
 }
 
 impl<T: ArrayValue> Array<T> {
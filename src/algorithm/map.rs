@@ -103,7 +103,7 @@ impl Value {
     pub fn get(&self, key: &Value, env: &Uiua) -> UiuaResult<Value> {
         // Check for higher-ranked keys
         if let Some(keys) = self.meta().map_keys.as_ref() {
-            if key.rank() == keys.keys.rank() && key.type_id() == keys.keys.type_id() {
+            if key.rank() == keys.keys.rank() && key.element_type() == keys.keys.element_type() {
                 let mut values = Vec::with_capacity(key.row_count());
                 for key in key.rows() {
                     values.push(self.get(&key, env)?);
@@ -140,7 +140,7 @@ impl Value {
     /// Check if a map array contains a key
     pub fn has_key(&self, key: &Value, env: &Uiua) -> UiuaResult<Array<u8>> {
         if let Some(keys) = self.meta().map_keys.as_ref() {
-            if key.rank() == keys.keys.rank() && key.type_id() == keys.keys.type_id() {
+            if key.rank() == keys.keys.rank() && key.element_type() == keys.keys.element_type() {
                 let mut values = EcoVec::with_capacity(key.row_count());
                 for key in key.rows() {
                     values.push(keys.get(&key).is_some().into());
@@ -159,7 +159,7 @@ impl Value {
     /// Insert a key-value pair into a map array
     #[allow(clippy::unit_arg)]
     pub fn insert(&mut self, key: Value, value: Value, env: &Uiua) -> UiuaResult {
-        // if value.rank() == self.rank() && value.type_id() == self.type_id() {
+        // if value.rank() == self.rank() && value.element_type() == self.element_type() {
         //     if key.row_count() != value.row_count() {
         //         return Err(env.error(format!(
         //             "You appear to be inserting multiple keys. \
@@ -290,7 +290,7 @@ impl Value {
     /// Remove a key-value pair from a map array
     pub fn remove(&mut self, key: Value, env: &Uiua) -> UiuaResult {
         if let Some(keys) = self.meta().map_keys.as_ref() {
-            if key.rank() == keys.keys.rank() && key.type_id() == keys.keys.type_id() {
+            if key.rank() == keys.keys.rank() && key.element_type() == keys.keys.element_type() {
                 for key in key.into_rows() {
                     self.remove(key, env)?;
                 }
@@ -18,7 +18,7 @@ use tinyvec::TinyVec;
 use crate::{
     cowslice::ecovec_extend_cowslice, fill::FillValue, Array, ArrayValue, Boxed, CodeSpan, Complex,
     ExactDoubleIterator, Inputs, Ops, PersistentMeta, Shape, SigNode, Signature, Span, Uiua,
-    UiuaError, UiuaErrorKind, UiuaResult, Value,
+    UiuaError, UiuaResult, Value,
 };
 
 mod dyadic;
@@ -190,12 +190,7 @@ impl ErrorContext for Uiua {
 impl ErrorContext for (&CodeSpan, &Inputs) {
     type Error = UiuaError;
     fn error(&self, msg: impl ToString) -> Self::Error {
-        UiuaErrorKind::Run {
-            message: Span::Code(self.0.clone()).sp(msg.to_string()),
-            info: Vec::new(),
-            inputs: self.1.clone().into(),
-        }
-        .into()
+        UiuaError::new(msg, self.0.clone()).with_inputs(self.1.clone())
     }
 }
 
@@ -326,6 +321,29 @@ pub(crate) fn shape_prefixes_match(a: &[usize], b: &[usize]) -> bool {
     a.iter().zip(b).all(|(a, b)| a == b)
 }
 
+/// Get the shape of the result of a pervasive operation on arrays with the given shapes
+///
+/// Corresponding axes must be equal, or one of them must be 1.
+/// Trailing axes are taken from the higher-rank shape.
+/// Fill values are not taken into account.
+pub fn broadcast_shape(a: &Shape, b: &Shape) -> UiuaResult<Shape> {
+    let mut shape = Shape::with_capacity(a.len().max(b.len()));
+    for i in 0..a.len().max(b.len()) {
+        shape.push(match (a.get(i).copied(), b.get(i).copied()) {
+            (Some(ad), Some(bd)) if ad == bd || ad == 1 || bd == 1 => pervade::pervade_dim(ad, bd),
+            (Some(_), Some(_)) => {
+                return Err(UiuaError::new(
+                    format!("Shapes {a} and {b} are not compatible"),
+                    Span::Builtin,
+                ))
+            }
+            (Some(d), None) | (None, Some(d)) => d,
+            (None, None) => unreachable!(),
+        });
+    }
+    Ok(shape)
+}
+
 fn fill_value_shape<C>(
     val: &mut Value,
     target: &Shape,
@@ -839,3 +857,65 @@ fn fft_impl(
     env.push(arr);
     Ok(())
 }
+
+impl Value {
+    /// Compute the full cross-correlation of two lists of numbers
+    ///
+    /// The result has length `len(a) + len(b) - 1`.
+    /// Element `k` is `Σᵢ a[i] × b[i + len(b) - 1 - k]`,
+    /// so the element at index `len(b) - 1` is the correlation at zero lag.
+    /// The result is not normalized.
+    pub fn correlate(a: &Value, b: &Value, env: &Uiua) -> UiuaResult<Value> {
+        let list = |val: &Value| -> UiuaResult<Vec<f64>> {
+            if val.rank() != 1 {
+                return Err(env.error(format!("Cannot correlate rank {} array", val.rank())));
+            }
+            match val {
+                Value::Num(arr) => Ok(arr.data.to_vec()),
+                Value::Byte(arr) => Ok(arr.data.iter().map(|&b| b as f64).collect()),
+                val => Err(env.error(format!("Cannot correlate {} array", val.type_name()))),
+            }
+        };
+        let a = list(a)?;
+        let mut b = list(b)?;
+        if a.is_empty() || b.is_empty() {
+            return Ok(Array::<f64>::default().into());
+        }
+        b.reverse();
+        validate_size::<f64>([a.len() + b.len() - 1], env)?;
+        Ok(Array::from_iter(convolve(&a, &b)).into())
+    }
+}
+
+#[cfg(feature = "fft")]
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    use rustfft::{num_complex::Complex64, FftPlanner};
+
+    let len = a.len() + b.len() - 1;
+    let pad = |list: &[f64]| {
+        let mut padded: Vec<Complex64> = list.iter().map(|&n| Complex64::new(n, 0.0)).collect();
+        padded.resize(len, Complex64::default());
+        padded
+    };
+    let (mut a, mut b) = (pad(a), pad(b));
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(len);
+    forward.process(&mut a);
+    forward.process(&mut b);
+    for (a, b) in a.iter_mut().zip(&b) {
+        *a *= b;
+    }
+    planner.plan_fft_inverse(len).process(&mut a);
+    a.into_iter().map(|c| c.re / len as f64).collect()
+}
+
+#[cfg(not(feature = "fft"))]
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, &a) in a.iter().enumerate() {
+        for (j, &b) in b.iter().enumerate() {
+            result[i + j] += a * b;
+        }
+    }
+    result
+}
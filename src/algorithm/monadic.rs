@@ -52,6 +52,30 @@ impl Value {
         let deshaped = self.shape_mut().split_off(depth).into_iter().product();
         self.shape_mut().push(deshaped);
     }
+    /// Turn the value into a list of its elements
+    ///
+    /// Unlike [`Value::deshape`], this does not look inside a scalar box.
+    /// The data is not copied.
+    pub fn flatten(mut self) -> Self {
+        self.take_map_keys();
+        *self.shape_mut() = self.element_count().into();
+        self
+    }
+    /// Turn the value into a list of its elements, recursively flattening and joining boxed values
+    pub fn flatten_boxed(self, env: &Uiua) -> UiuaResult<Self> {
+        let Value::Box(arr) = self else {
+            return Ok(self.flatten());
+        };
+        let mut flat: Option<Value> = None;
+        for Boxed(val) in arr.data {
+            let val = val.flatten_boxed(env)?;
+            flat = Some(match flat {
+                Some(flat) => flat.join(val, false, env)?,
+                None => val,
+            });
+        }
+        Ok(flat.unwrap_or_default())
+    }
     pub(crate) fn deshape_sub(&mut self, irank: i32, extend: bool, env: &Uiua) -> UiuaResult {
         if irank > 0 && irank as usize == self.rank() {
             return Ok(());
@@ -1026,6 +1050,44 @@ impl Value {
     pub fn count_unique(&self) -> usize {
         val_as_arr!(self, Array::count_unique)
     }
+    /// Run-length encode a list
+    ///
+    /// Returns the first element of each run of equal elements
+    /// and the length of each run.
+    pub fn encode_rle(val: Value, env: &Uiua) -> UiuaResult<(Value, Value)> {
+        if val.rank() != 1 {
+            return Err(env.error(format!(
+                "Cannot run-length encode rank {} array",
+                val.rank()
+            )));
+        }
+        Ok(val_as_arr!(val, |arr| {
+            let (values, lengths) = arr.encode_rle();
+            (values.into(), lengths.into())
+        }))
+    }
+    /// Decode a run-length encoded list
+    ///
+    /// This is the inverse of [`Value::encode_rle`].
+    pub fn decode_rle(values: Value, lengths: Value, env: &Uiua) -> UiuaResult<Value> {
+        if values.rank() != 1 || lengths.rank() != 1 {
+            return Err(env.error(format!(
+                "Run-length decoding requires two lists, \
+                but the values have rank {} and the lengths have rank {}",
+                values.rank(),
+                lengths.rank()
+            )));
+        }
+        if values.row_count() != lengths.row_count() {
+            return Err(env.error(format!(
+                "Cannot run-length decode {} values with {} lengths",
+                values.row_count(),
+                lengths.row_count()
+            )));
+        }
+        lengths.as_nats(env, "Run lengths must be natural numbers")?;
+        lengths.keep(values, env)
+    }
     /// Check that all values are true
     pub fn all_true(&self) -> bool {
         match self {
@@ -1039,6 +1101,21 @@ impl Value {
 }
 
 impl<T: ArrayValue> Array<T> {
+    /// Run-length encode a list
+    fn encode_rle(&self) -> (Self, Array<f64>) {
+        let mut values = EcoVec::new();
+        let mut lengths: Vec<f64> = Vec::new();
+        for elem in self.data.iter() {
+            match (values.last(), lengths.last_mut()) {
+                (Some(last), Some(len)) if elem.array_eq(last) => *len += 1.0,
+                _ => {
+                    values.push(elem.clone());
+                    lengths.push(1.0);
+                }
+            }
+        }
+        (Array::new(values.len(), values), Array::from_iter(lengths))
+    }
     /// Get the `rise` of the array
     pub fn rise(&self) -> Array<f64> {
         if self.rank() == 0 {
@@ -1890,6 +1967,86 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    /// Get the index of the maximum element
+    ///
+    /// For arrays of rank 2 or more, this reduces along the last axis.
+    /// Ties resolve to the first index.
+    pub fn argmax(&self, env: &Uiua) -> UiuaResult<Self> {
+        val_as_arr!(self, |arr| arr.arg_extremum(Ordering::Greater, env)).map(Into::into)
+    }
+    /// Get the index of the minimum element
+    ///
+    /// For arrays of rank 2 or more, this reduces along the last axis.
+    /// Ties resolve to the first index.
+    pub fn argmin(&self, env: &Uiua) -> UiuaResult<Self> {
+        val_as_arr!(self, |arr| arr.arg_extremum(Ordering::Less, env)).map(Into::into)
+    }
+}
+
+impl Value {
+    /// Compute the `n`th order differences along an axis
+    ///
+    /// Each difference is a row minus the row before it.
+    /// The length of the axis decreases by `n`.
+    pub fn diff(mut self, axis: usize, n: usize, env: &Uiua) -> UiuaResult<Self> {
+        if axis >= self.rank() {
+            return Err(env.error(format!(
+                "Cannot get differences along axis {axis} of a rank {} array",
+                self.rank()
+            )));
+        }
+        // Move the axis to the front
+        self.transpose_depth(0, axis as i32);
+        for _ in 0..n {
+            let len = self.row_count();
+            if len == 0 {
+                break;
+            }
+            let next = self.slice_rows(1, len);
+            let prev = self.slice_rows(0, len - 1);
+            self = prev.sub(next, env)?;
+        }
+        self.transpose_depth(0, -(axis as i32));
+        Ok(self)
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    fn arg_extremum(&self, wanted: Ordering, env: &Uiua) -> UiuaResult<Array<f64>> {
+        let name = if wanted == Ordering::Greater {
+            "argmax"
+        } else {
+            "argmin"
+        };
+        if self.rank() == 0 {
+            return Ok(0.0.into());
+        }
+        let axis_len = *self.shape.last().unwrap();
+        let mut shape = self.shape.clone();
+        shape.pop();
+        if axis_len == 0 {
+            let fill = env
+                .scalar_fill::<f64>()
+                .map_err(|e| env.error(format!("Cannot get {name} of an empty axis{e}")))?;
+            let data = eco_vec![fill.value; shape.elements()];
+            return Ok(Array::new(shape, data));
+        }
+        let data: EcoVec<f64> = (self.data.chunks_exact(axis_len))
+            .map(|lane| {
+                let mut best = 0;
+                for (i, elem) in lane.iter().enumerate().skip(1) {
+                    if elem.array_cmp(&lane[best]) == wanted {
+                        best = i;
+                    }
+                }
+                best as f64
+            })
+            .collect();
+        Ok(Array::new(shape, data))
+    }
+}
+
 impl Value {
     pub(crate) fn primes(&self, env: &Uiua) -> UiuaResult<Array<f64>> {
         match self {
@@ -2083,7 +2240,7 @@ impl Value {
                     rows.push(value);
                 }
                 if rows.iter().all(|val| val.shape().is_empty())
-                    && (rows.windows(2)).all(|win| win[0].type_id() == win[1].type_id())
+                    && (rows.windows(2)).all(|win| win[0].element_type() == win[1].element_type())
                 {
                     Value::from_row_values_infallible(rows)
                 } else {
@@ -2105,7 +2262,7 @@ impl Value {
                     values.push(value);
                 }
                 let mut values = if values.iter().all(|val| val.shape().is_empty())
-                    && (values.windows(2)).all(|win| win[0].type_id() == win[1].type_id())
+                    && (values.windows(2)).all(|win| win[0].element_type() == win[1].element_type())
                 {
                     Value::from_row_values_infallible(values)
                 } else {
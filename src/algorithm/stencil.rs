@@ -267,6 +267,57 @@ where
     })
 }
 
+impl Value {
+    /// Split the rows of the value into non-overlapping chunks of the given size
+    ///
+    /// If the number of rows is not a multiple of the size, the last chunk is filled.
+    /// It is an error if there is no fill value set in that case.
+    pub fn chunks(self, size: usize, env: &Uiua) -> UiuaResult<Self> {
+        let mut val = self;
+        val.match_fill(env);
+        Ok(val_as_arr!(val, |arr| arr.chunks(size, env)?.into()))
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    /// Split the rows of the array into non-overlapping chunks of the given size
+    ///
+    /// If the number of rows is not a multiple of the size, the last chunk is filled.
+    /// It is an error if there is no fill value set in that case.
+    pub fn chunks(mut self, size: usize, env: &Uiua) -> UiuaResult<Self> {
+        if size == 0 {
+            return Err(env.error("Chunk size must be positive"));
+        }
+        if self.rank() == 0 {
+            return Err(env.error("Cannot split a scalar into chunks"));
+        }
+        let rem = self.row_count() % size;
+        if rem != 0 {
+            let fill = env.scalar_fill::<T>().map_err(|e| {
+                env.error(format!(
+                    "Cannot split {} rows into chunks of {size}{e}",
+                    self.row_count()
+                ))
+            })?;
+            let mut target = self.shape.to_vec();
+            target[0] = target[0].checked_add(size - rem).ok_or_else(|| {
+                env.error(format!(
+                    "Cannot fill {} rows to a multiple of {size}",
+                    self.row_count()
+                ))
+            })?;
+            validate_size::<T>(target.iter().copied(), env)?;
+            self.fill_to_shape(&target, fill);
+        }
+        let chunk_count = self.row_count() / size;
+        self.shape[0] = size;
+        self.shape.insert(0, chunk_count);
+        self.take_map_keys();
+        self.validate_shape();
+        Ok(self)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct WindowDim {
     size: usize,
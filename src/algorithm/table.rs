@@ -170,6 +170,36 @@ fn generic_table(f: SigNode, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult
     Ok(())
 }
 
+impl Value {
+    /// Call a function on every pair of elements of two values
+    ///
+    /// The result has the shape of `a` followed by the shape of `b`,
+    /// followed by the shape of the function's results.
+    pub fn outer_product<F>(a: Value, b: Value, mut f: F, env: &mut Uiua) -> UiuaResult<Value>
+    where
+        F: FnMut(Value, Value, &mut Uiua) -> UiuaResult<Value>,
+    {
+        validate_size::<f64>([a.element_count(), b.element_count()], env)?;
+        let mut new_shape = a.shape().clone();
+        new_shape.extend_from_slice(b.shape());
+        let mut items = Value::builder(a.element_count() * b.element_count());
+        let b_elems: Vec<Value> = b.into_elements().collect();
+        env.without_fill(|env| -> UiuaResult {
+            for a_elem in a.into_elements() {
+                for b_elem in b_elems.iter().cloned() {
+                    items.add_row(f(a_elem.clone(), b_elem, env)?, env)?;
+                }
+            }
+            Ok(())
+        })?;
+        let mut tabled = items.finish();
+        new_shape.extend_from_slice(&tabled.shape()[1..]);
+        *tabled.shape_mut() = new_shape;
+        tabled.validate_shape();
+        Ok(tabled)
+    }
+}
+
 pub fn table_list(f: SigNode, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     validate_size::<f64>([f.sig.outputs, xs.row_count(), ys.row_count()], env)?;
@@ -381,6 +381,21 @@ impl<T> Array<T> {
         let row_len = self.row_len();
         &self.data[row * row_len..(row + 1) * row_len]
     }
+    /// Get an iterator over borrowed views of the rows of the array
+    ///
+    /// Unlike [`Array::rows`], this does not allocate or clone any data
+    pub fn iter_rows_ref(
+        &self,
+    ) -> impl ExactSizeIterator<Item = ArrayView<'_, T>> + DoubleEndedIterator {
+        self.view().rows()
+    }
+    /// Get a borrowed view of the whole array
+    pub fn view(&self) -> ArrayView<'_, T> {
+        ArrayView {
+            shape: &self.shape,
+            data: &self.data,
+        }
+    }
     /// Combine the metadata of two arrays
     ///
     /// This combines:
@@ -397,6 +412,72 @@ impl<T> Array<T> {
     }
 }
 
+/// A borrowed view into an [`Array`] or part of one
+///
+/// Created by [`Array::view`] and [`Array::iter_rows_ref`]
+#[derive(Debug)]
+pub struct ArrayView<'a, T> {
+    shape: &'a [usize],
+    data: &'a [T],
+}
+
+impl<T> Clone for ArrayView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArrayView<'_, T> {}
+
+impl<'a, T> ArrayView<'a, T> {
+    /// Get the shape of the view
+    pub fn shape(&self) -> &'a [usize] {
+        self.shape
+    }
+    /// Get the elements of the view
+    pub fn data(&self) -> &'a [T] {
+        self.data
+    }
+    /// Get the rank of the view
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+    /// Get the number of rows in the view
+    pub fn row_count(&self) -> usize {
+        self.shape.first().copied().unwrap_or(1)
+    }
+    /// Get the number of elements in a row
+    pub fn row_len(&self) -> usize {
+        self.shape.iter().skip(1).product()
+    }
+    /// Get an iterator over borrowed views of the rows of the view
+    pub fn rows(self) -> impl ExactSizeIterator<Item = Self> + DoubleEndedIterator {
+        let shape = self.shape.get(1..).unwrap_or_default();
+        let row_len = self.row_len();
+        (0..self.row_count()).map(move |row| ArrayView {
+            shape,
+            data: &self.data[row * row_len..(row + 1) * row_len],
+        })
+    }
+    /// Copy the view into an owned [`Array`]
+    pub fn to_array(&self) -> Array<T>
+    where
+        T: Clone,
+    {
+        Array::new(self.shape, self.data)
+    }
+}
+
+impl<T: ArrayValue> ArrayView<'_, T> {
+    /// Copy the view into an owned [`Value`]
+    pub fn to_value(&self) -> Value
+    where
+        Array<T>: Into<Value>,
+    {
+        self.to_array().into()
+    }
+}
+
 impl<T: ArrayValue> Array<T> {
     /// Create a scalar array
     pub fn scalar(data: T) -> Self {
@@ -0,0 +1,151 @@
+//! Conversion between Uiua values and Apache Arrow arrays
+
+use std::sync::Arc;
+
+use arrow_array::{
+    cast::AsArray, types::*, Array as ArrowArray, ArrayRef, ArrowPrimitiveType, FixedSizeListArray,
+    Float64Array, StringArray,
+};
+use arrow_schema::{DataType, Field};
+use ecow::EcoVec;
+
+use crate::{Array, Boxed, Shape, Span, UiuaError, UiuaResult, Value};
+
+fn arrow_error(message: impl Into<String>) -> UiuaError {
+    UiuaError::new(message.into(), Span::Builtin)
+}
+
+impl Value {
+    /// Convert the value to an Arrow array
+    ///
+    /// - Rank 1 number arrays become `Float64Array`s
+    /// - Character arrays become `StringArray`s with one string per rank 1 row
+    /// - Rank 1 arrays of boxed strings become `StringArray`s
+    /// - Other rank 2 arrays become `FixedSizeListArray`s of their rows
+    pub fn to_arrow(&self) -> UiuaResult<ArrayRef> {
+        Ok(match self {
+            Value::Char(arr) if arr.rank() <= 2 => {
+                let strings: Vec<String> = if arr.rank() <= 1 {
+                    vec![arr.data.iter().collect()]
+                } else {
+                    arr.row_slices().map(|row| row.iter().collect()).collect()
+                };
+                Arc::new(StringArray::from(strings))
+            }
+            Value::Box(arr) if arr.rank() == 1 => {
+                let strings = (arr.data.iter())
+                    .map(|Boxed(val)| match val {
+                        Value::Char(s) if s.rank() == 1 => Ok(s.data.iter().collect()),
+                        val => Err(arrow_error(format!(
+                            "Only boxed strings can be converted to Arrow, \
+                            but the array contains a {}",
+                            val.type_name()
+                        ))),
+                    })
+                    .collect::<UiuaResult<Vec<String>>>()?;
+                Arc::new(StringArray::from(strings))
+            }
+            Value::Num(arr) if arr.rank() == 1 => Arc::new(Float64Array::from(arr.data.to_vec())),
+            Value::Byte(arr) if arr.rank() == 1 => Arc::new(Float64Array::from_iter_values(
+                arr.data.iter().map(|&b| b as f64),
+            )),
+            _ if self.rank() == 2 => {
+                let size = i32::try_from(self.shape()[1]).map_err(|_| {
+                    arrow_error(format!(
+                        "Rows of length {} are too long for Arrow",
+                        self.shape()[1]
+                    ))
+                })?;
+                let mut flat = self.clone();
+                flat.deshape();
+                let values = flat.to_arrow()?;
+                let field = Field::new_list_field(values.data_type().clone(), false);
+                let list = FixedSizeListArray::try_new(Arc::new(field), size, values, None)
+                    .map_err(|e| arrow_error(e.to_string()))?;
+                Arc::new(list)
+            }
+            _ => {
+                return Err(arrow_error(format!(
+                    "Cannot convert rank {} {} array to Arrow",
+                    self.rank(),
+                    self.type_name()
+                )))
+            }
+        })
+    }
+    /// Convert an Arrow array to a value
+    ///
+    /// Numeric and boolean arrays become number arrays.
+    /// Null elements become `NaN`.
+    /// String arrays become lists of boxed strings. They may not contain nulls.
+    /// `FixedSizeListArray`s without nulls become arrays with an additional axis.
+    pub fn from_arrow(array: &dyn ArrowArray) -> UiuaResult<Self> {
+        fn numeric<T: ArrowPrimitiveType>(
+            array: &dyn ArrowArray,
+            f: impl Fn(T::Native) -> f64,
+        ) -> UiuaResult<Value> {
+            let arr = (array.as_primitive_opt::<T>())
+                .ok_or_else(|| arrow_error("Arrow array does not match its data type"))?;
+            let data: EcoVec<f64> = arr.iter().map(|n| n.map_or(f64::NAN, &f)).collect();
+            Ok(Array::new(data.len(), data).into())
+        }
+        fn strings<'a>(strings: impl Iterator<Item = Option<&'a str>>) -> UiuaResult<Value> {
+            let data = (strings.enumerate())
+                .map(|(i, s)| {
+                    let s = s.ok_or_else(|| {
+                        arrow_error(format!("Arrow string array has a null at index {i}"))
+                    })?;
+                    Ok(Boxed(s.into()))
+                })
+                .collect::<UiuaResult<EcoVec<Boxed>>>()?;
+            Ok(Array::new(data.len(), data).into())
+        }
+        let mismatch = || arrow_error("Arrow array does not match its data type");
+        match array.data_type() {
+            DataType::Float64 => numeric::<Float64Type>(array, |n| n),
+            DataType::Float32 => numeric::<Float32Type>(array, |n| n as f64),
+            DataType::Int8 => numeric::<Int8Type>(array, |n| n as f64),
+            DataType::Int16 => numeric::<Int16Type>(array, |n| n as f64),
+            DataType::Int32 => numeric::<Int32Type>(array, |n| n as f64),
+            DataType::Int64 => numeric::<Int64Type>(array, |n| n as f64),
+            DataType::UInt8 => numeric::<UInt8Type>(array, |n| n as f64),
+            DataType::UInt16 => numeric::<UInt16Type>(array, |n| n as f64),
+            DataType::UInt32 => numeric::<UInt32Type>(array, |n| n as f64),
+            DataType::UInt64 => numeric::<UInt64Type>(array, |n| n as f64),
+            DataType::Boolean => {
+                let arr = array.as_boolean_opt().ok_or_else(mismatch)?;
+                let data: EcoVec<f64> = (arr.iter())
+                    .map(|b| b.map_or(f64::NAN, |b| b as u8 as f64))
+                    .collect();
+                Ok(Array::new(data.len(), data).into())
+            }
+            DataType::Utf8 => strings(array.as_string_opt::<i32>().ok_or_else(mismatch)?.iter()),
+            DataType::LargeUtf8 => {
+                strings(array.as_string_opt::<i64>().ok_or_else(mismatch)?.iter())
+            }
+            DataType::FixedSizeList(..) => {
+                let arr = array.as_fixed_size_list_opt().ok_or_else(mismatch)?;
+                if arr.null_count() > 0 {
+                    return Err(arrow_error("Arrow list array has null lists"));
+                }
+                let size = arr.value_length() as usize;
+                let mut val = Value::from_arrow(arr.values().as_ref())?;
+                if val.row_count() != arr.len() * size {
+                    return Err(arrow_error(format!(
+                        "Arrow list array of {} lists of length {size} has {} values",
+                        arr.len(),
+                        val.row_count()
+                    )));
+                }
+                let mut shape = Shape::from([arr.len(), size]);
+                shape.extend_from_slice(&val.shape()[1..]);
+                *val.shape_mut() = shape;
+                val.validate_shape();
+                Ok(val)
+            }
+            data_type => Err(arrow_error(format!(
+                "Cannot convert Arrow {data_type:?} array to a Uiua value"
+            ))),
+        }
+    }
+}
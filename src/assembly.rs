@@ -1,10 +1,14 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     hash::{DefaultHasher, Hash, Hasher},
     ops::{Index, IndexMut},
     path::PathBuf,
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicUsize},
+        Arc,
+    },
 };
 
 use dashmap::DashMap;
@@ -12,9 +16,10 @@ use ecow::{eco_vec, EcoString, EcoVec};
 use serde::*;
 
 use crate::{
+    check::{nodes_clean_sig, nodes_sig},
     compile::{LocalName, Module},
-    is_ident_char, CodeSpan, FunctionId, InputSrc, IntoInputSrc, Node, SigNode, Signature, Span,
-    Uiua, UiuaResult, Value,
+    is_ident_char, CodeSpan, FunctionId, InputSrc, IntoInputSrc, Node, Primitive, Purity, SigNode,
+    Signature, Span, Uiua, UiuaError, UiuaResult, Value,
 };
 
 /// A compiled Uiua assembly
@@ -33,6 +38,8 @@ pub struct Assembly {
     pub inputs: Inputs,
     pub(crate) dynamic_functions: EcoVec<DynFn>,
     pub(crate) test_assert_count: usize,
+    /// Whether the assembly was compiled with [`RunMode::Dry`](crate::RunMode::Dry)
+    pub(crate) dry_run: bool,
 }
 
 /// A Uiua function
@@ -169,6 +176,358 @@ impl Assembly {
     pub(crate) fn def(&self, index: usize) -> &DefInfo {
         &self.defs[index]
     }
+    /// Rename a global binding
+    ///
+    /// The binding must be the only one with the given name.
+    /// References to the binding are by index, so they remain valid.
+    pub fn rename_binding(&mut self, old_name: &str, new_name: &str) -> UiuaResult<RenameResult> {
+        if new_name.is_empty() || !new_name.chars().all(is_ident_char) {
+            return Err(self.error(format!("`{new_name}` is not a valid binding name")));
+        }
+        let index = self.unique_binding(old_name)?;
+        if !self.bindings_named(new_name).is_empty() {
+            return Err(self.error(format!("A binding named `{new_name}` already exists")));
+        }
+        // Function bindings are called through their function rather than their index
+        let func = match &self.bindings[index].kind {
+            BindingKind::Func(f) => Some(f),
+            _ => None,
+        };
+        fn count_calls(node: &Node, index: usize, func: Option<&Function>) -> usize {
+            let count = |node: &Node| count_calls(node, index, func);
+            match node {
+                Node::CallGlobal(i, _) | Node::CallMacro { index: i, .. } => (*i == index) as usize,
+                Node::Call(f, _) => (Some(f) == func) as usize,
+                Node::Run(nodes) => nodes.iter().map(count).sum(),
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    args.iter().map(|sn| count(&sn.node)).sum()
+                }
+                Node::Switch { branches, .. } => branches.iter().map(|sn| count(&sn.node)).sum(),
+                Node::CustomInverse(cust, _) => cust.nodes().map(|sn| count(&sn.node)).sum(),
+                Node::Array { inner, .. } => count(inner),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => count(&inner.node),
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    count(inner)
+                }
+                _ => 0,
+            }
+        }
+        let call_sites = count_calls(&self.root, index, func)
+            + (self.functions.iter())
+                .map(|node| count_calls(node, index, func))
+                .sum::<usize>();
+        self.bindings.make_mut()[index].span = CodeSpan::literal(new_name);
+        Ok(RenameResult { index, call_sites })
+    }
+    fn error(&self, message: String) -> UiuaError {
+        UiuaError::new(message, Span::Builtin).with_inputs(self.inputs.clone())
+    }
+    fn bindings_named(&self, name: &str) -> Vec<usize> {
+        (self.bindings.iter().enumerate())
+            .filter(|(_, binding)| binding.span.as_str(&self.inputs, |s| s == name))
+            .map(|(i, _)| i)
+            .collect()
+    }
+    fn unique_binding(&self, name: &str) -> UiuaResult<usize> {
+        match self.bindings_named(name).as_slice() {
+            [] => Err(self.error(format!("No binding named `{name}`"))),
+            [index] => Ok(*index),
+            indices => Err(self.error(format!(
+                "There are {} bindings named `{name}`",
+                indices.len()
+            ))),
+        }
+    }
+    /// Extract a function and everything it depends on into a standalone assembly
+    ///
+    /// The new assembly's root calls the function.
+    /// Bindings and functions that the function does not reference are dropped,
+    /// so the remaining ones are renumbered.
+    /// Constants that are only evaluated when the original root runs have no value.
+    pub fn extract_function(&self, name: &str) -> UiuaResult<Assembly> {
+        let index = self.unique_binding(name)?;
+        let BindingKind::Func(f) = &self.bindings[index].kind else {
+            return Err(self.error(format!("`{name}` is not a function")));
+        };
+        // Collect dependencies
+        type Deps = (BTreeSet<usize>, BTreeSet<usize>);
+        fn binding(index: usize, asm: &Assembly, deps: &mut Deps) {
+            if !deps.0.insert(index) {
+                return;
+            }
+            match &asm.bindings[index].kind {
+                BindingKind::Func(f) => function(f, asm, deps),
+                BindingKind::CodeMacro(node) => collect(node, asm, deps),
+                _ => {}
+            }
+        }
+        fn function(f: &Function, asm: &Assembly, deps: &mut Deps) {
+            if deps.1.insert(f.index) {
+                collect(&asm[f], asm, deps)
+            }
+        }
+        fn collect(node: &Node, asm: &Assembly, deps: &mut Deps) {
+            match node {
+                Node::Run(nodes) => nodes.iter().for_each(|node| collect(node, asm, deps)),
+                Node::CallGlobal(index, _)
+                | Node::CallMacro { index, .. }
+                | Node::BindGlobal { index, .. } => binding(*index, asm, deps),
+                Node::Call(f, _) => function(f, asm, deps),
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    args.iter().for_each(|sn| collect(&sn.node, asm, deps))
+                }
+                Node::Switch { branches, .. } => {
+                    branches.iter().for_each(|sn| collect(&sn.node, asm, deps))
+                }
+                Node::CustomInverse(cust, _) => {
+                    cust.nodes().for_each(|sn| collect(&sn.node, asm, deps))
+                }
+                Node::Array { inner, .. } => collect(inner, asm, deps),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    collect(&inner.node, asm, deps)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    collect(inner, asm, deps)
+                }
+                _ => {}
+            }
+        }
+        let mut deps = Deps::default();
+        binding(index, self, &mut deps);
+        // Renumber bindings and functions
+        type Renumber = (HashMap<usize, usize>, HashMap<usize, usize>);
+        let renumber: Renumber = (
+            deps.0.iter().enumerate().map(|(i, &j)| (j, i)).collect(),
+            deps.1.iter().enumerate().map(|(i, &j)| (j, i)).collect(),
+        );
+        fn renumber_node(node: &mut Node, renumber: &Renumber) {
+            let recurse = |node: &mut Node| renumber_node(node, renumber);
+            match node {
+                Node::Run(nodes) => nodes.make_mut().iter_mut().for_each(recurse),
+                Node::CallGlobal(index, _)
+                | Node::CallMacro { index, .. }
+                | Node::BindGlobal { index, .. } => *index = renumber.0[index],
+                Node::Call(f, _) => f.index = renumber.1[&f.index],
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    (args.make_mut().iter_mut()).for_each(|sn| recurse(&mut sn.node))
+                }
+                Node::Switch { branches, .. } => {
+                    (branches.make_mut().iter_mut()).for_each(|sn| recurse(&mut sn.node))
+                }
+                Node::CustomInverse(cust, _) => {
+                    (Arc::make_mut(cust).nodes_mut()).for_each(|sn| recurse(&mut sn.node))
+                }
+                Node::Array { inner, .. } => recurse(Arc::make_mut(inner)),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&mut Arc::make_mut(inner).node)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    recurse(Arc::make_mut(inner))
+                }
+                _ => {}
+            }
+        }
+        let renumber_function = |f: &Function| Function {
+            index: renumber.1[&f.index],
+            ..f.clone()
+        };
+        let functions = (deps.1.iter())
+            .map(|&i| {
+                let mut node = self.functions[i].clone();
+                renumber_node(&mut node, &renumber);
+                node
+            })
+            .collect();
+        let bindings = (deps.0.iter())
+            .map(|&i| {
+                let mut binding = self.bindings[i].clone();
+                match &mut binding.kind {
+                    BindingKind::Func(f) => *f = renumber_function(f),
+                    BindingKind::CodeMacro(node) => renumber_node(node, &renumber),
+                    _ => {}
+                }
+                binding
+            })
+            .collect();
+        Ok(Assembly {
+            root: Node::Call(renumber_function(f), 0),
+            functions,
+            bindings,
+            defs: self.defs.clone(),
+            spans: self.spans.clone(),
+            inputs: self.inputs.clone(),
+            dynamic_functions: self.dynamic_functions.clone(),
+            test_assert_count: 0,
+            dry_run: false,
+        })
+    }
+    /// Evaluate pure constant sub-expressions and replace them with their values
+    ///
+    /// Only sequences of pushes and pure primitives are folded.
+    /// Modifier bodies are not folded, because they may run while a fill is set,
+    /// and fills change the behavior of many primitives.
+    /// The root is assumed to run without a fill, as it does with [`Uiua::run_asm`].
+    /// Sub-expressions that fail, take too many steps to evaluate,
+    /// or produce large values are left as they are.
+    pub fn constant_fold(&mut self) {
+        let steps = Arc::new(AtomicUsize::new(0));
+        let mut env = Uiua::with_safe_sys().with_interrupt_hook({
+            let steps = steps.clone();
+            move || steps.fetch_add(1, atomic::Ordering::Relaxed) >= FOLD_STEP_LIMIT
+        });
+        env.asm.spans = self.spans.clone();
+        env.asm.inputs = self.inputs.clone();
+        let mut folder = Folder { env, steps };
+        folder.fold_node(&mut self.root);
+        // Functions are always called behind a fill boundary
+        for node in self.functions.make_mut() {
+            folder.fold_node(node);
+        }
+    }
+    /// Check the assembly for internal inconsistencies
+    ///
+    /// A correctly compiled assembly should never have any.
+    /// This is mainly useful for testing the compiler.
+    /// Each function is reported at most once, no matter how many places call it.
+    pub fn verify_integrity(&self) -> Vec<IntegrityError> {
+        let mut errors = Vec::new();
+        let mut checked = BTreeSet::new();
+        let mut check_function = |f: &Function, errors: &mut Vec<IntegrityError>| {
+            if !checked.insert(f.index) {
+                return;
+            }
+            let Some(node) = self.functions.get(f.index) else {
+                errors.push(IntegrityError::InvalidFunction(f.index));
+                return;
+            };
+            if let Ok(inferred) = nodes_sig(node.as_slice()) {
+                if inferred != f.sig {
+                    errors.push(IntegrityError::SignatureMismatch {
+                        function: f.id.clone(),
+                        declared: f.sig,
+                        inferred,
+                    });
+                }
+            }
+        };
+        for binding in &self.bindings {
+            if let BindingKind::Func(f) = &binding.kind {
+                check_function(f, &mut errors);
+            }
+        }
+        fn walk(
+            node: &Node,
+            asm: &Assembly,
+            errors: &mut Vec<IntegrityError>,
+            check_function: &mut impl FnMut(&Function, &mut Vec<IntegrityError>),
+        ) {
+            let mut recurse = |node: &Node, errors: &mut Vec<IntegrityError>| {
+                walk(node, asm, errors, check_function)
+            };
+            if !matches!(node, Node::Run(_)) {
+                if let Some(span) = node.span().filter(|&span| span >= asm.spans.len()) {
+                    errors.push(IntegrityError::InvalidSpan(span));
+                }
+            }
+            match node {
+                Node::Run(nodes) => nodes.iter().for_each(|node| recurse(node, errors)),
+                Node::CallGlobal(index, _)
+                | Node::CallMacro { index, .. }
+                | Node::BindGlobal { index, .. }
+                    if *index >= asm.bindings.len() =>
+                {
+                    errors.push(IntegrityError::InvalidGlobal(*index))
+                }
+                Node::Call(f, _) => check_function(f, errors),
+                Node::Dynamic(df) if df.index >= asm.dynamic_functions.len() => {
+                    errors.push(IntegrityError::InvalidDynamicFunction(df.index))
+                }
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    args.iter().for_each(|sn| recurse(&sn.node, errors))
+                }
+                Node::Switch { branches, .. } => {
+                    branches.iter().for_each(|sn| recurse(&sn.node, errors))
+                }
+                Node::CustomInverse(cust, _) => {
+                    cust.nodes().for_each(|sn| recurse(&sn.node, errors))
+                }
+                Node::Array { inner, .. } => recurse(inner, errors),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, errors)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    recurse(inner, errors)
+                }
+                _ => {}
+            }
+        }
+        walk(&self.root, self, &mut errors, &mut check_function);
+        for node in &self.functions {
+            walk(node, self, &mut errors, &mut check_function);
+        }
+        errors
+    }
+    /// Get the graph of which bound functions call which bindings
+    ///
+    /// Calls made through unnamed functions are attributed to the binding that contains them.
+    pub fn call_graph(&self) -> CallGraph {
+        let bound_functions: HashMap<usize, usize> = (self.bindings.iter().enumerate())
+            .filter_map(|(i, binding)| match &binding.kind {
+                BindingKind::Func(f) => Some((f.index, i)),
+                _ => None,
+            })
+            .collect();
+        // The unnamed functions visited so far and the bindings called
+        type Calls = (BTreeSet<usize>, BTreeSet<usize>);
+        fn walk(
+            node: &Node,
+            asm: &Assembly,
+            bound_functions: &HashMap<usize, usize>,
+            calls: &mut Calls,
+        ) {
+            let recurse = |node: &Node, calls: &mut Calls| walk(node, asm, bound_functions, calls);
+            match node {
+                Node::Run(nodes) => nodes.iter().for_each(|node| recurse(node, calls)),
+                Node::CallGlobal(index, _) | Node::CallMacro { index, .. } => {
+                    calls.1.insert(*index);
+                }
+                Node::Call(f, _) => {
+                    if let Some(&index) = bound_functions.get(&f.index) {
+                        calls.1.insert(index);
+                    } else if calls.0.insert(f.index) {
+                        recurse(&asm[f], calls)
+                    }
+                }
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    args.iter().for_each(|sn| recurse(&sn.node, calls))
+                }
+                Node::Switch { branches, .. } => {
+                    branches.iter().for_each(|sn| recurse(&sn.node, calls))
+                }
+                Node::CustomInverse(cust, _) => {
+                    cust.nodes().for_each(|sn| recurse(&sn.node, calls))
+                }
+                Node::Array { inner, .. } => recurse(inner, calls),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, calls)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    recurse(inner, calls)
+                }
+                _ => {}
+            }
+        }
+        let mut calls = HashMap::new();
+        for (i, binding) in self.bindings.iter().enumerate() {
+            let BindingKind::Func(f) = &binding.kind else {
+                continue;
+            };
+            let mut callees = Calls::default();
+            walk(&self[f], self, &bound_functions, &mut callees);
+            calls.insert(i, callees.1.into_iter().collect());
+        }
+        CallGraph { calls }
+    }
     /// Parse a `.uasm` file into an assembly
     pub fn from_uasm(src: &str) -> Result<Self, String> {
         let rest = src;
@@ -266,6 +625,7 @@ impl Assembly {
             },
             dynamic_functions: EcoVec::new(),
             test_assert_count: 0,
+            dry_run: false,
         })
     }
     /// Serialize the assembly into a `.uasm` file
@@ -373,6 +733,7 @@ impl Default for Assembly {
             dynamic_functions: EcoVec::new(),
             inputs: Inputs::default(),
             test_assert_count: 0,
+            dry_run: false,
         }
     }
 }
@@ -396,6 +757,118 @@ pub struct BindingInfo {
     pub meta: BindingMeta,
 }
 
+/// The result of [`Assembly::rename_binding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenameResult {
+    /// The index of the renamed binding
+    pub index: usize,
+    /// The number of call sites that reference the binding
+    pub call_sites: usize,
+}
+
+/// The result of [`Assembly::call_graph`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallGraph {
+    /// Map the index of each bound function to the indices of the bindings it calls
+    pub calls: HashMap<usize, Vec<usize>>,
+}
+
+impl CallGraph {
+    /// Get the indices of the bindings a binding calls
+    pub fn callees(&self, index: usize) -> &[usize] {
+        self.calls
+            .get(&index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+    /// Check if a binding can end up calling itself
+    pub fn is_recursive(&self, index: usize) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut stack = self.callees(index).to_vec();
+        while let Some(i) = stack.pop() {
+            if i == index {
+                return true;
+            }
+            if visited.insert(i) {
+                stack.extend_from_slice(self.callees(i));
+            }
+        }
+        false
+    }
+    /// Order the bindings so that every binding comes before the bindings it calls
+    ///
+    /// Returns `None` if there is a cycle.
+    pub fn topological_order(&self) -> Option<Vec<usize>> {
+        let mut callers: BTreeMap<usize, usize> = BTreeMap::new();
+        for (&caller, callees) in &self.calls {
+            callers.entry(caller).or_default();
+            for &callee in callees {
+                *callers.entry(callee).or_default() += 1;
+            }
+        }
+        let mut ready: Vec<usize> = (callers.iter())
+            .filter(|(_, &count)| count == 0)
+            .map(|(&i, _)| i)
+            .rev()
+            .collect();
+        let mut order = Vec::with_capacity(callers.len());
+        while let Some(i) = ready.pop() {
+            order.push(i);
+            for callee in self.callees(i) {
+                let count = callers.get_mut(callee).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(*callee);
+                }
+            }
+        }
+        (order.len() == callers.len()).then_some(order)
+    }
+}
+
+/// An inconsistency found by [`Assembly::verify_integrity`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// A node refers to a binding that does not exist
+    InvalidGlobal(usize),
+    /// A node refers to a span that does not exist
+    InvalidSpan(usize),
+    /// A function refers to a body that does not exist
+    InvalidFunction(usize),
+    /// A node refers to a dynamic function that does not exist
+    InvalidDynamicFunction(usize),
+    /// A function's signature does not match the signature of its body
+    SignatureMismatch {
+        /// The function's id
+        function: FunctionId,
+        /// The function's signature
+        declared: Signature,
+        /// The signature of the function's body
+        inferred: Signature,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::InvalidGlobal(i) => write!(f, "Binding {i} does not exist"),
+            IntegrityError::InvalidSpan(i) => write!(f, "Span {i} does not exist"),
+            IntegrityError::InvalidFunction(i) => write!(f, "Function {i} does not exist"),
+            IntegrityError::InvalidDynamicFunction(i) => {
+                write!(f, "Dynamic function {i} does not exist")
+            }
+            IntegrityError::SignatureMismatch {
+                function,
+                declared,
+                inferred,
+            } => write!(
+                f,
+                "{function} has signature {declared}, but its body has signature {inferred}"
+            ),
+        }
+    }
+}
+
 /// Metadata about a binding
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct BindingMeta {
@@ -812,3 +1285,72 @@ impl fmt::Debug for Assembly {
             .finish()
     }
 }
+
+/// The maximum number of instructions evaluated for a single folded sub-expression
+const FOLD_STEP_LIMIT: usize = 10_000;
+/// The maximum total number of elements in the values of a folded sub-expression
+const FOLD_SIZE_LIMIT: usize = 1_000;
+
+/// Evaluates sub-expressions for [`Assembly::constant_fold`]
+struct Folder {
+    env: Uiua,
+    steps: Arc<AtomicUsize>,
+}
+
+impl Folder {
+    fn fold_node(&mut self, node: &mut Node) {
+        match node {
+            Node::Run(nodes) => nodes.make_mut().iter_mut().for_each(|n| self.fold_node(n)),
+            Node::Array { inner, .. } => self.fold_node(Arc::make_mut(inner)),
+            _ => {}
+        }
+        if let Some(folded) = self.fold_nodes(node.as_slice()) {
+            *node = folded;
+        }
+    }
+    fn fold_nodes(&mut self, nodes: &[Node]) -> Option<Node> {
+        let foldable = |node: &Node| match node {
+            Node::Push(_) => true,
+            Node::Prim(Primitive::Sys(_) | Primitive::Send | Primitive::Recv, _) => false,
+            Node::Prim(prim, _) => prim.purity() == Purity::Pure,
+            Node::ImplPrim(prim, _) => prim.purity() == Purity::Pure,
+            _ => false,
+        };
+        let mut new: Option<Node> = None;
+        let mut start = 0;
+        while start < nodes.len() {
+            // Find the longest foldable section that takes no arguments
+            let mut best = None;
+            let mut end = start;
+            while end < nodes.len() && foldable(&nodes[end]) {
+                end += 1;
+                let section = &nodes[start..end];
+                if !section.iter().all(|node| matches!(node, Node::Push(_)))
+                    && nodes_clean_sig(section).is_some_and(|sig| sig.args == 0)
+                {
+                    best = Some(end);
+                }
+            }
+            if let Some(end) = best {
+                if let Some(values) = self.eval(&nodes[start..end]) {
+                    let new = new.get_or_insert_with(|| nodes[..start].into());
+                    new.extend(values.into_iter().map(Node::Push));
+                    start = end;
+                    continue;
+                }
+            }
+            if let Some(new) = &mut new {
+                new.push(nodes[start].clone());
+            }
+            start += 1;
+        }
+        new
+    }
+    fn eval(&mut self, section: &[Node]) -> Option<Vec<Value>> {
+        self.steps.store(0, atomic::Ordering::Relaxed);
+        let res = self.env.exec(Node::from(section));
+        let values = self.env.take_stack();
+        let size: usize = values.iter().map(Value::element_count).sum();
+        (res.is_ok() && size <= FOLD_SIZE_LIMIT).then_some(values)
+    }
+}
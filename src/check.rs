@@ -112,7 +112,10 @@ impl Stack {
         self.height += n as i32;
         self.stack.extend(repeat(BasicValue::Other).take(n));
     }
+    // Simulate removing the value at depth `i`, which requires at least `i + 1` values.
     fn remove(&mut self, i: usize) -> BasicValue {
+        let min_height = (i as i32 + 1 - self.height).max(0) as usize;
+        self.min_height = self.min_height.max(min_height);
         self.height -= 1;
         if i < self.stack.len() {
             self.stack.remove(self.stack.len() - i - 1)
@@ -691,6 +694,10 @@ impl VirtualEnv {
                 }
             }
             Node::TrackCaller(inner) | Node::NoInline(inner) => self.node(inner)?,
+            Node::CommentedOut(inner) => {
+                // Still check the inner node, but it has no effect on the stack
+                inner.sig()?;
+            }
             Node::WithLocal { inner, .. } => {
                 let _val = self.stack.remove(inner.sig.args);
                 self.node(&inner.node)?;
@@ -698,6 +705,9 @@ impl VirtualEnv {
             Node::GetLocal { .. } => self.handle_args_outputs(0, 1),
             Node::SetLocal { .. } => self.handle_args_outputs(1, 0),
             Node::NormalizeSoA { .. } => self.handle_args_outputs(1, 1),
+            Node::Repeat { count, inner, .. } => {
+                self.repeat(inner, BasicValue::Num(*count as f64))?
+            }
         }
         // println!("{node:?} -> {} ({})", self.stack.sig(), self.under.sig());
         Ok(())
@@ -28,7 +28,7 @@ impl Compiler {
         // Get data def if this is a method
         let mut data_def = None;
         let is_method = if let Some(tilde_span) = binding.tilde_span {
-            self.experimental_error(&tilde_span, || {
+            self.experimental_feature_error(FeatureFlags::METHODS, &tilde_span, || {
                 "Methods are experimental. To use them, add \
                 `# Experimental!` to the top of the file."
             });
@@ -222,13 +222,13 @@ impl Compiler {
             }
             (n, Some(max)) => {
                 if max + 1 > n {
-                    self.emit_diagnostic(
+                    self.emit_warning(
                         format!(
                             "`{name}`'s name suggest at most ^{}, \
                             but it contains a ^{max}",
                             n - 1
                         ),
-                        DiagnosticKind::Warning,
+                        DiagnosticCategory::MacroName,
                         span.clone(),
                     );
                 }
@@ -41,6 +41,9 @@ impl Compiler {
                 };
 
                 let comment = prelude.comment.clone();
+                if data.variant {
+                    (self.variant_tags).insert(global_index, self.scope.data_variants);
+                }
                 let (module, ()) = self
                     .in_scope(ScopeKind::Module(name.value.clone()), |comp| {
                         comp.data_def(data, false, prelude)
@@ -110,10 +113,14 @@ impl Compiler {
                 });
                 // Compile validator
                 let validator_and_inv = if let Some(validator) = data_field.validator {
-                    self.experimental_error(&data.init_span, || {
-                        "Field validators are experimental. To use them, add \
+                    self.experimental_feature_error(
+                        FeatureFlags::FIELD_VALIDATORS,
+                        &data.init_span,
+                        || {
+                            "Field validators are experimental. To use them, add \
                         `# Experimental!` to the top of the file."
-                    });
+                        },
+                    );
                     let mut validator = self.words_sig(validator.words)?;
                     if validator.sig.args != 1 {
                         self.add_error(
@@ -398,6 +405,19 @@ impl Compiler {
                 node.push(Node::ImplPrim(ImplPrimitive::TagVariant, span));
             }
         }
+        if let Some(eq_span) = &prelude.eq {
+            if def_name.is_none() || !has_fields || fields.is_empty() {
+                self.add_error(
+                    eq_span.clone(),
+                    "Only named data definitions with fields can derive equality",
+                );
+            }
+            // Label instances so that defs with the same fields can be told apart.
+            // Variant tags are already labeled.
+            if let Some(name) = def_name.clone().filter(|_| !data.variant) {
+                node.push(Node::Label(name, span));
+            }
+        }
         let constructor_name = Ident::from("New");
         let constructor_func = self.asm.add_function(
             FunctionId::Named(constructor_name.clone()),
@@ -498,7 +518,7 @@ impl Compiler {
         let mut function_stuff = None;
         // Data functions
         if let Some(words) = data.func {
-            self.experimental_error(&data.init_span, || {
+            self.experimental_feature_error(FeatureFlags::DATA_FUNCTIONS, &data.init_span, || {
                 "Data functions are experimental. To use them, add \
                 `# Experimental!` to the top of the file."
             });
@@ -518,14 +538,12 @@ impl Compiler {
                         span,
                     );
                 }
-                let node = Node::from_iter([
-                    construct,
-                    Node::WithLocal {
-                        def: def_index,
-                        inner: inner.into(),
-                        span,
-                    },
-                ]);
+                let with_node = Node::WithLocal {
+                    def: def_index,
+                    inner: inner.into(),
+                    span,
+                };
+                let node = Node::from_iter([construct, with_node.clone()]);
                 let sig = comp.sig_of(&node, &word_span)?;
                 let local = LocalName {
                     index: comp.next_global,
@@ -535,14 +553,129 @@ impl Compiler {
                 let func =
                     comp.asm
                         .add_function(FunctionId::Named(constructor_name.clone()), sig, node);
-                function_stuff = Some((local, func, span));
+                // Call the function with an existing instance
+                let with_sig = comp.sig_of(&with_node, &word_span)?;
+                let with_local = LocalName {
+                    index: comp.next_global,
+                    public: true,
+                };
+                comp.next_global += 1;
+                let with_func =
+                    comp.asm
+                        .add_function(FunctionId::Named("With".into()), with_sig, with_node);
+                function_stuff = Some((local, func, with_local, with_func, span));
                 Ok(())
             })?;
         }
 
         // Bind the call function
-        if let Some((local, func, span)) = function_stuff {
+        if let Some((local, func, with_local, with_func, span)) = function_stuff {
             self.compile_bind_function("Call".into(), local, func, span, BindingMeta::default())?;
+            let comment = match &def_name {
+                Some(def_name) => format!("Call `{def_name}`'s function with an existing instance"),
+                None => "Call the data function with an existing instance".into(),
+            };
+            let meta = BindingMeta {
+                comment: Some(DocComment::from(comment.as_str())),
+                ..Default::default()
+            };
+            self.compile_bind_function("With".into(), with_local, with_func, span, meta)?;
+        }
+
+        // Bind the equality function
+        if let Some(def_name) = def_name.as_ref().filter(|_| prelude.eq.is_some()) {
+            // Variant instances start with their tag
+            let tag: Array<f64> = (data.variant.then_some(variant_index as f64))
+                .into_iter()
+                .collect();
+            let len = fields.len() + data.variant as usize;
+            let node = Node::from_iter([
+                Node::new_push(len),
+                Node::new_push(boxed),
+                Node::new_push(tag),
+                Node::new_push(def_name.as_str()),
+                Node::ImplPrim(ImplPrimitive::MatchDef, span),
+            ]);
+            let comment = format!("Check if two `{def_name}`s are equal\nEqual ? A B");
+            self.bind_derived_function("Eq", Signature::new(2, 1), node, span, comment)?;
+        }
+
+        // Bind the JSON conversion functions
+        if prelude.serialize {
+            if !has_fields || fields.is_empty() {
+                self.add_error(
+                    data.init_span.clone(),
+                    "Only data definitions with fields can derive serialization",
+                );
+            }
+            // To JSON
+            let mut inner = Node::empty();
+            for (i, field) in fields.iter().enumerate().rev() {
+                if i > 0 {
+                    inner.push(Node::Prim(Primitive::Dup, span));
+                }
+                let getter = self.global_index(field.global_index, true, field.name_span.clone());
+                inner.push(getter);
+                if i > 0 {
+                    inner.push(Node::Prim(Primitive::Flip, span));
+                }
+            }
+            let names: Value = Array::from_iter(fields.iter().map(|f| f.name.as_str())).into();
+            let node = Node::from_iter([
+                Node::Array {
+                    len: ArrayLen::Static(fields.len()),
+                    inner: inner.into(),
+                    boxed: true,
+                    allow_ext: false,
+                    prim: None,
+                    span,
+                },
+                Node::new_push(names),
+                Node::Prim(Primitive::Map, span),
+                Node::Prim(Primitive::Json, span),
+            ]);
+            let comment = match &def_name {
+                Some(def_name) => format!("Encode a `{def_name}` as a JSON object"),
+                None => "Encode a data instance as a JSON object".into(),
+            };
+            self.bind_derived_function("ToJson", Signature::new(1, 1), node, span, comment)?;
+            // From JSON
+            let mut json_fields = Vec::new();
+            for field in &fields {
+                match field.init.as_ref().map_or(1, |sn| sn.sig.args) {
+                    0 => {}
+                    1 => json_fields.push(field),
+                    n => self.add_error(
+                        field.name_span.clone(),
+                        format!(
+                            "Cannot derive serialization because `{}`'s \
+                            initializer takes {n} arguments",
+                            field.name
+                        ),
+                    ),
+                }
+            }
+            let mut node = Node::ImplPrim(ImplPrimitive::UnJson, span);
+            if json_fields.is_empty() {
+                node.push(Node::Prim(Primitive::Pop, span));
+            }
+            for (i, field) in json_fields.iter().enumerate().rev() {
+                if i > 0 {
+                    node.push(Node::Prim(Primitive::Dup, span));
+                }
+                node.push(Node::new_push(field.name.as_str()));
+                node.push(Node::Prim(Primitive::Get, span));
+                node.push(Node::ImplPrim(ImplPrimitive::UnBox, span));
+                if i > 0 {
+                    node.push(Node::Prim(Primitive::Flip, span));
+                }
+            }
+            node.push(Node::Call(constructor_func.clone(), span));
+            let comment = match &def_name {
+                Some(def_name) => format!("Decode a `{def_name}` from a JSON object"),
+                None => "Decode a data instance from a JSON object".into(),
+            };
+            self.bind_derived_function("FromJson", Signature::new(1, 1), node, span, comment)?;
         }
 
         // Bind the SoA constructor
@@ -599,6 +732,27 @@ impl Compiler {
 
         Ok(())
     }
+    fn bind_derived_function(
+        &mut self,
+        name: &str,
+        sig: Signature,
+        node: Node,
+        span: usize,
+        comment: String,
+    ) -> UiuaResult {
+        let name = Ident::from(name);
+        let func = (self.asm).add_function(FunctionId::Named(name.clone()), sig, node);
+        let local = LocalName {
+            index: self.next_global,
+            public: true,
+        };
+        self.next_global += 1;
+        let meta = BindingMeta {
+            comment: Some(DocComment::from(comment.as_str())),
+            ..Default::default()
+        };
+        self.compile_bind_function(name, local, func, span, meta)
+    }
     pub(super) fn end_enum(&mut self) -> UiuaResult {
         Ok(())
     }
@@ -573,6 +573,13 @@ under!(RowsPat, input, g_sig, inverse, asm, {
 });
 
 under!(RepeatPat, input, g_sig, inverse, asm, {
+    if let [Node::Repeat { count, inner, span }, input @ ..] = input {
+        let (f_before, f_after) = inner.under_inverse(g_sig, inverse, asm)?;
+        let count = Push((*count).into());
+        let befores = Node::from_iter([count.clone(), Mod(Repeat, eco_vec![f_before], *span)]);
+        let afters = Node::from_iter([count, Mod(Repeat, eco_vec![f_after], *span)]);
+        return Ok((input, befores, afters));
+    }
     let (input, val) = if let Ok((input, val)) = Val.invert_extract(input, asm) {
         (input, Some(val))
     } else {
@@ -12,6 +12,7 @@ use std::{
     collections::{BTreeSet, HashMap, HashSet, VecDeque},
     env::current_dir,
     fmt, fs,
+    io::Write,
     iter::once,
     mem::{replace, swap, take},
     panic::{catch_unwind, AssertUnwindSafe},
@@ -20,24 +21,27 @@ use std::{
     sync::Arc,
 };
 
+use bitflags::bitflags;
 use ecow::{eco_vec, EcoString, EcoVec};
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     ast::*,
     check::{nodes_sig, SigCheckErrorKind},
-    format::{format_word, format_words},
+    format::{format_str, format_word, format_words, FormatConfig},
     function::DynamicFunction,
     ident_modifier_args,
     lex::{CodeSpan, Sp, Span},
     lsp::{CodeMeta, ImportSrc, SetInverses, SigDecl},
     parse::{flip_unsplit_lines, max_placeholder, parse, split_words},
+    run::ImportHook,
     Array, ArrayLen, Assembly, BindingKind, BindingMeta, Boxed, CustomInverse, Diagnostic,
-    DiagnosticKind, DocComment, DocCommentSig, Function, FunctionId, GitTarget, Ident,
-    ImplPrimitive, InputSrc, IntoInputSrc, IntoSysBackend, Node, PrimClass, Primitive, Purity,
-    RunMode, SemanticComment, SigNode, Signature, SysBackend, Uiua, UiuaError, UiuaErrorKind,
-    UiuaResult, Value, CONSTANTS, EXAMPLE_UA, SUBSCRIPT_DIGITS, VERSION,
+    DiagnosticCategory, DiagnosticKind, DocComment, DocCommentSig, ElementType, Function,
+    FunctionId, GitTarget, Ident, ImplPrimitive, InputSrc, IntoInputSrc, IntoSysBackend, Node,
+    PrimClass, Primitive, Purity, RunMode, SemanticComment, Shape, SigNode, Signature, SysBackend,
+    SysOp, Uiua, UiuaError, UiuaErrorKind, UiuaResult, Value, CONSTANTS, EXAMPLE_UA,
+    SUBSCRIPT_DIGITS, VERSION,
 };
 pub use pre_eval::PreEvalMode;
 
@@ -55,7 +59,7 @@ pub struct Compiler {
     /// Ancestor scopes of the current one
     higher_scopes: Vec<Scope>,
     /// Determines which How test scopes are run
-    mode: RunMode,
+    pub(crate) mode: RunMode,
     /// The paths of files currently being imported (used to detect import cycles)
     current_imports: Vec<PathBuf>,
     /// The bindings of imported files
@@ -64,8 +68,12 @@ pub struct Compiler {
     index_macros: HashMap<usize, IndexMacro>,
     /// Unexpanded code macros
     code_macros: HashMap<usize, CodeMacro>,
+    /// Modifiers implemented in Rust
+    dynamic_modifiers: HashMap<usize, DynamicModifier>,
     /// Indices of named external functions
     externals: HashMap<Ident, usize>,
+    /// Map variant module indices to their variant tags
+    variant_tags: HashMap<usize, usize>,
     /// The depth of compile-time evaluation
     comptime_depth: usize,
     /// Whether the compiler is in a try
@@ -86,6 +94,14 @@ pub struct Compiler {
     macro_env: Uiua,
     /// Start addresses
     start_addrs: Vec<usize>,
+    /// Experimental features enabled for all files
+    feature_flags: FeatureFlags,
+    /// The language edition to compile for
+    edition: UiuaEdition,
+    /// Warning categories that are reported as errors
+    warnings_as_errors: HashSet<DiagnosticCategory>,
+    /// Overrides resolution of import paths to source code
+    import_resolver: Option<ImportResolver>,
 }
 
 impl Default for Compiler {
@@ -102,7 +118,9 @@ impl Default for Compiler {
             imports: HashMap::new(),
             index_macros: HashMap::new(),
             code_macros: HashMap::new(),
+            dynamic_modifiers: HashMap::new(),
             externals: HashMap::new(),
+            variant_tags: HashMap::new(),
             comptime_depth: 0,
             in_try: false,
             errors: Vec::new(),
@@ -113,10 +131,45 @@ impl Default for Compiler {
             pre_eval_mode: PreEvalMode::default(),
             macro_env: Uiua::default(),
             start_addrs: Vec::new(),
+            feature_flags: FeatureFlags::empty(),
+            edition: UiuaEdition::default(),
+            warnings_as_errors: HashSet::new(),
+            import_resolver: None,
         }
     }
 }
 
+bitflags! {
+    /// Experimental features that can be enabled for all compiled files
+    ///
+    /// See [`Compiler::with_feature_flags`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct FeatureFlags: u32 {
+        /// Validators on data definition fields
+        const FIELD_VALIDATORS = 1;
+        /// Data functions
+        const DATA_FUNCTIONS = 1 << 1;
+        /// Methods on data definitions
+        const METHODS = 1 << 2;
+        /// Inline macros
+        const INLINE_MACROS = 1 << 3;
+    }
+}
+
+/// A version of the language
+///
+/// Deprecated primitives are removed in later editions.
+/// See [`Compiler::set_edition`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum UiuaEdition {
+    /// Deprecated primitives can still be used
+    #[default]
+    Edition2024,
+    /// Deprecated primitives are errors
+    Edition2025,
+}
+
 #[derive(Debug, Default)]
 struct BindingPrelude {
     comment: Option<EcoString>,
@@ -124,10 +177,75 @@ struct BindingPrelude {
     no_inline: bool,
     external: bool,
     deprecation: Option<EcoString>,
+    eq: Option<CodeSpan>,
+    serialize: bool,
 }
 
 type LocalNames = IndexMap<Ident, LocalName>;
 
+/// A mismatch between a function's declared signature and the signature of its body
+///
+/// Returned by [`Compiler::check_signatures`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureError {
+    /// The id of the function
+    pub id: FunctionId,
+    /// The signature the function was declared with
+    pub declared: Signature,
+    /// The signature inferred from the function's body
+    pub inferred: Signature,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is declared as {} but its body has signature {}",
+            self.id, self.declared, self.inferred
+        )
+    }
+}
+
+/// A description of a public binding
+///
+/// Returned by [`Compiler::export_interface`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingInterface {
+    /// The name of the binding
+    pub name: Ident,
+    /// What the binding is
+    pub kind: InterfaceKind,
+    /// The binding's documentation comment
+    pub doc: Option<String>,
+}
+
+/// The kind of a [`BindingInterface`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceKind {
+    /// A constant with the given element type and shape
+    Constant(ElementType, Shape),
+    /// A function with the given signature
+    Function(Signature),
+    /// A module with the given public bindings
+    Module(Vec<BindingInterface>),
+}
+
+/// A reference to a node in an [`Assembly`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeRef {
+    /// The index of the function that contains the node, or `None` for top-level code
+    pub function: Option<usize>,
+    /// The position of the node in a pre-order traversal of the function's body
+    ///
+    /// [`Node::Run`]s are not counted.
+    pub position: usize,
+}
+
+/// A mapping from nodes to their source spans
+///
+/// Returned by [`Compiler::source_map`]
+pub type SourceMap = Vec<(NodeRef, Span)>;
+
 /// A Uiua module
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Module {
@@ -156,6 +274,26 @@ struct CodeMacro {
     names: IndexMap<Ident, LocalName>,
 }
 
+/// A modifier implemented in Rust
+#[derive(Clone)]
+struct DynamicModifier {
+    sig: DynModSig,
+    f: DynModFn,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type DynModSig = Arc<dyn Fn(&[Signature]) -> Result<Signature, String> + Send + Sync + 'static>;
+#[cfg(target_arch = "wasm32")]
+type DynModSig = Arc<dyn Fn(&[Signature]) -> Result<Signature, String> + 'static>;
+#[cfg(not(target_arch = "wasm32"))]
+type DynModFn = Arc<dyn Fn(&[SigNode], &mut Uiua) -> UiuaResult + Send + Sync + 'static>;
+#[cfg(target_arch = "wasm32")]
+type DynModFn = Arc<dyn Fn(&[SigNode], &mut Uiua) -> UiuaResult + 'static>;
+#[cfg(not(target_arch = "wasm32"))]
+type ImportResolver = Arc<dyn Fn(&Path, &str) -> UiuaResult<String> + Send + Sync + 'static>;
+#[cfg(target_arch = "wasm32")]
+type ImportResolver = Arc<dyn Fn(&Path, &str) -> UiuaResult<String> + 'static>;
+
 impl AsRef<Assembly> for Compiler {
     fn as_ref(&self) -> &Assembly {
         &self.asm
@@ -297,8 +435,38 @@ impl Compiler {
     pub fn code_meta_mut(&mut self) -> &mut CodeMeta {
         &mut self.code_meta
     }
+    /// Get the documentation comment of a top-level binding
+    pub fn get_doc(&self, name: &str) -> Option<&str> {
+        let local = self.scope.names.get(name)?;
+        self.binding_doc(local)
+    }
+    /// Get the documentation comment of a public binding in a module
+    ///
+    /// The module may be either a scoped module or an imported module bound to a name.
+    pub fn get_module_doc(&self, module_name: &str, binding_name: &str) -> Option<&str> {
+        let local = self.scope.names.get(module_name)?;
+        let module = match &self.asm.bindings.get(local.index)?.kind {
+            BindingKind::Module(module) => module,
+            BindingKind::Import(path) => self.imports.get(path)?,
+            _ => return None,
+        };
+        let local = module
+            .names
+            .get(binding_name)
+            .filter(|local| local.public)?;
+        self.binding_doc(local)
+    }
+    fn binding_doc(&self, local: &LocalName) -> Option<&str> {
+        let binding = self.asm.bindings.get(local.index)?;
+        binding
+            .meta
+            .comment
+            .as_ref()
+            .map(|comment| comment.text.as_str())
+    }
     /// Take a completed assembly from the compiler
     pub fn finish(&mut self) -> Assembly {
+        self.asm.dry_run = self.mode == RunMode::Dry;
         take(&mut self.asm)
     }
     /// Set whether to evaluate `comptime`
@@ -350,6 +518,72 @@ impl Compiler {
     pub fn set_backend<T: SysBackend>(&mut self, backend: T) {
         self.macro_env.rt.backend = Arc::new(backend);
     }
+    /// Enable experimental features without `# Experimental!`
+    ///
+    /// This applies to every file the compiler loads, including imports.
+    pub fn with_feature_flags(mut self, flags: FeatureFlags) -> Self {
+        self.feature_flags = flags;
+        self
+    }
+    /// Set the language edition to compile for
+    ///
+    /// This applies to every file the compiler loads, including imports.
+    pub fn set_edition(mut self, edition: UiuaEdition) -> Self {
+        self.edition = edition;
+        self
+    }
+    /// Report warnings of a category as errors
+    ///
+    /// Code that produces such a warning fails to compile.
+    pub fn with_warning_as_error(mut self, category: DiagnosticCategory) -> Self {
+        self.warnings_as_errors.insert(category);
+        self
+    }
+    /// Set a hook for resolving imports
+    ///
+    /// See [`Uiua::with_import_hook`].
+    /// The hook is only a fallback for the file system and Git.
+    /// It is not called while an import resolver is set.
+    pub fn with_import_hook(
+        mut self,
+        hook: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.set_import_hook(Some(Arc::new(hook)));
+        self
+    }
+    /// Set a function that resolves all imports to source code
+    ///
+    /// The resolver is passed the path of the importing file and the import string.
+    /// For top-level code, the importer path is empty.
+    /// When set, neither the file system nor Git is used for imports,
+    /// and any import hook is ignored, whether it was set before or after the resolver.
+    /// Errors from the resolver are not retried with the hook.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_import_resolver(
+        mut self,
+        resolver: impl Fn(&Path, &str) -> UiuaResult<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.import_resolver = Some(Arc::new(resolver));
+        self
+    }
+    /// Set a function that resolves all imports to source code
+    ///
+    /// The resolver is passed the path of the importing file and the import string.
+    /// For top-level code, the importer path is empty.
+    /// When set, neither the file system nor Git is used for imports,
+    /// and any import hook is ignored, whether it was set before or after the resolver.
+    /// Errors from the resolver are not retried with the hook.
+    #[cfg(target_arch = "wasm32")]
+    pub fn with_import_resolver(
+        mut self,
+        resolver: impl Fn(&Path, &str) -> UiuaResult<String> + 'static,
+    ) -> Self {
+        self.import_resolver = Some(Arc::new(resolver));
+        self
+    }
+    pub(crate) fn set_import_hook(&mut self, hook: Option<ImportHook>) {
+        self.macro_env.rt.import_hook = hook;
+    }
     /// Compile a Uiua file from a file at a path
     pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> UiuaResult<&mut Self> {
         let path = path.as_ref();
@@ -365,6 +599,21 @@ impl Compiler {
         let src = self.asm.inputs.add_src((), input);
         self.load_impl(input, src)
     }
+    /// Parse a string without compiling it
+    ///
+    /// The input is added to the assembly's inputs so that spans in the items are valid.
+    /// Parse diagnostics are emitted as if the string were compiled.
+    pub fn parse_only(&mut self, input: &str) -> UiuaResult<Vec<Item>> {
+        let src = self.asm.inputs.add_src((), input);
+        let (items, errors, diagnostics) = parse(input, src, &mut self.asm.inputs);
+        for diagnostic in diagnostics {
+            self.emit_diagnostic_impl(diagnostic);
+        }
+        if !errors.is_empty() {
+            return Err(UiuaErrorKind::Parse(errors, self.asm.inputs.clone().into()).into());
+        }
+        Ok(items)
+    }
     /// Compile a Uiua file from a string with a path for error reporting
     pub fn load_str_src(&mut self, input: &str, src: impl IntoInputSrc) -> UiuaResult<&mut Self> {
         let src = self.asm.inputs.add_src(src, input);
@@ -587,8 +836,19 @@ code:
                 item_errored = true;
             }
         }
+        self.reject_derive_eq(&mut prelude);
         Ok(())
     }
+    /// Error if a `# Eq!` comment is not followed by a data definition
+    fn reject_derive_eq(&mut self, prelude: &mut BindingPrelude) {
+        if let Some(span) = prelude.eq.take() {
+            self.add_error(span, "# Eq! can only be used on data definitions");
+        }
+    }
+    fn reset_prelude(&mut self, prelude: &mut BindingPrelude) {
+        self.reject_derive_eq(prelude);
+        *prelude = BindingPrelude::default();
+    }
     fn item(
         &mut self,
         item: Item,
@@ -596,6 +856,9 @@ code:
         must_run: bool,
         prelude: &mut BindingPrelude,
     ) -> UiuaResult {
+        if let Item::Module(_) | Item::Binding(_) | Item::Import(_) = &item {
+            self.reject_derive_eq(prelude);
+        }
         match item {
             Item::Module(m) => self.module(m, take(prelude)),
             Item::Words(lines) => self.top_level_words(lines, from_macro, must_run, true, prelude),
@@ -649,20 +912,26 @@ code:
                         prelude.track_caller = true
                     }
                     Word::SemanticComment(SemanticComment::External) => prelude.external = true,
+                    Word::SemanticComment(SemanticComment::DeriveEq) => {
+                        prelude.eq = Some(word.span.clone())
+                    }
+                    Word::SemanticComment(SemanticComment::DeriveSerialize) => {
+                        prelude.serialize = true
+                    }
                     Word::SemanticComment(SemanticComment::Deprecated(s)) => {
                         prelude.deprecation = Some(s.clone())
                     }
-                    _ => *prelude = BindingPrelude::default(),
+                    _ => self.reset_prelude(prelude),
                 }
             } else {
-                *prelude = BindingPrelude::default();
+                self.reset_prelude(prelude);
             }
         }
         let in_test = self.scopes().any(|sc| sc.kind == ScopeKind::Test);
         let can_run = match self.mode {
             RunMode::Normal => !in_test,
             RunMode::Test => in_test,
-            RunMode::All => true,
+            RunMode::All | RunMode::Dry => true,
         };
         let mut lines = VecDeque::from(flip_unsplit_lines(
             lines.into_iter().flat_map(split_words).collect(),
@@ -778,14 +1047,14 @@ code:
     ) -> UiuaResult {
         if let Some(sig) = meta.comment.as_ref().and_then(|c| c.sig.as_ref()) {
             if !sig.matches_sig(function.sig) {
-                self.emit_diagnostic(
+                self.emit_warning(
                     format!(
                         "{name}'s comment describes {}, \
                         but its code has signature {}",
                         sig.sig_string(),
                         function.sig,
                     ),
-                    DiagnosticKind::Warning,
+                    DiagnosticCategory::SignatureMismatch,
                     self.get_span(span).clone().code().unwrap(),
                 );
             }
@@ -810,12 +1079,12 @@ code:
     ) {
         let span = self.get_span(span).clone().code().unwrap();
         if let Some(sig) = meta.comment.as_ref().and_then(|c| c.sig.as_ref()) {
-            self.emit_diagnostic(
+            self.emit_warning(
                 format!(
                     "{name}'s comment describes {}, but it is a constant",
                     sig.sig_string(),
                 ),
-                DiagnosticKind::Warning,
+                DiagnosticCategory::SignatureMismatch,
                 span.clone(),
             );
         }
@@ -825,8 +1094,24 @@ code:
     }
     /// Import a module
     pub(crate) fn import_module(&mut self, path_str: &str, span: &CodeSpan) -> UiuaResult<PathBuf> {
+        let importer = self.current_imports.last().cloned().unwrap_or_default();
+        let resolved = (self.import_resolver.clone())
+            .map(|resolver| resolver(&importer, path_str))
+            .transpose()
+            .map_err(|e| self.error(span.clone(), e))?;
+        let resolver_used = resolved.is_some();
+        let hooked = resolved
+            .or_else(|| (self.macro_env.rt.import_hook.as_ref()).and_then(|hook| hook(path_str)));
         // Resolve path
-        let (path, file_kind) = if let Some(mut url) = path_str.trim().strip_prefix("git:") {
+        let (path, file_kind) = if resolver_used {
+            // Custom resolution
+            let path = (importer.parent())
+                .map(|parent| parent.join(path_str))
+                .unwrap_or_else(|| path_str.into());
+            (path, FileScopeKind::Source)
+        } else if let Some(mut url) =
+            (path_str.trim().strip_prefix("git:")).filter(|_| hooked.is_none())
+        {
             if url.contains("branch:") && url.contains("commit:") {
                 return Err(self.error(
                     span.clone(),
@@ -871,26 +1156,34 @@ code:
             thread_local! {
                 static GIT_CACHE: RefCell<HashMap<PathBuf, Compiler>> = RefCell::new(HashMap::new());
             }
-            let bytes = self
-                .backend()
-                .file_read_all(&path)
-                .or_else(|e| {
-                    if path.ends_with(Path::new("example.ua")) {
-                        Ok(EXAMPLE_UA.as_bytes().to_vec())
-                    } else {
-                        Err(e)
-                    }
-                })
-                .map_err(|e| self.error(span.clone(), e))?;
+            let bytes = if let Some(src) = hooked {
+                src.into_bytes()
+            } else {
+                self.backend()
+                    .file_read_all(&path)
+                    .or_else(|e| {
+                        if path.ends_with(Path::new("example.ua")) {
+                            Ok(EXAMPLE_UA.as_bytes().to_vec())
+                        } else {
+                            Err(e)
+                        }
+                    })
+                    .map_err(|e| self.error(span.clone(), e))?
+            };
             if let Some(mut comp) = (bytes.len() > 1000)
                 .then(|| GIT_CACHE.with(|cache| cache.borrow().get(&path).cloned()))
                 .flatten()
             {
                 swap(self, &mut comp);
                 self.macro_env.rt.backend = comp.macro_env.rt.backend;
+                self.macro_env.rt.import_hook = comp.macro_env.rt.import_hook;
                 self.asm.inputs.strings = comp.asm.inputs.strings;
                 self.asm.inputs.files.extend(comp.asm.inputs.files);
                 self.scope.experimental = comp.scope.experimental;
+                self.feature_flags = comp.feature_flags;
+                self.edition = comp.edition;
+                self.warnings_as_errors = comp.warnings_as_errors;
+                self.import_resolver = comp.import_resolver;
                 self.diagnostics.extend(comp.diagnostics);
             } else {
                 let input: EcoString = String::from_utf8(bytes)
@@ -1000,13 +1293,13 @@ code:
             }
             if !comment_sig.matches_sig(sig) {
                 let span = *spandex.get_or_insert_with(|| self.add_span(span.clone()));
-                self.emit_diagnostic(
+                self.emit_warning(
                     format!(
                         "{name} comment describes {}, \
                         but its code has signature {sig}",
                         comment_sig.sig_string()
                     ),
-                    DiagnosticKind::Warning,
+                    DiagnosticCategory::SignatureMismatch,
                     self.get_span(span).clone().code().unwrap(),
                 );
             }
@@ -1549,9 +1842,9 @@ code:
                     }
                     node.prepend(dip);
                 }
-                self.emit_diagnostic(
+                self.emit_warning(
                     format!("Signature mismatch: declared {new_sig} but inferred {sig}"),
-                    DiagnosticKind::Warning,
+                    DiagnosticCategory::SignatureMismatch,
                     span.clone(),
                 );
             }
@@ -1565,13 +1858,13 @@ code:
                     extra = Node::Mod(Primitive::Dip, eco_vec![extra.sig_node().unwrap()], spandex);
                 }
                 node.push(extra);
-                self.emit_diagnostic(
+                self.emit_warning(
                     format!(
                         "Signature mismatch: declared {new_sig} but inferred {sig}. \
                         {diff} debug output{} will be generated.",
                         if diff == 1 { "" } else { "s" }
                     ),
-                    DiagnosticKind::Warning,
+                    DiagnosticCategory::SignatureMismatch,
                     span.clone(),
                 );
             }
@@ -1584,12 +1877,12 @@ code:
                     pops = Node::Mod(Primitive::Dip, eco_vec![pops.sig_node().unwrap()], spandex);
                 }
                 node.push(pops);
-                self.emit_diagnostic(
+                self.emit_warning(
                     format!(
                         "Signature mismatch: declared {new_sig} but inferred {sig}. \
                         Additional arguments will be popped."
                     ),
-                    DiagnosticKind::Warning,
+                    DiagnosticCategory::SignatureMismatch,
                     span.clone(),
                 );
             }
@@ -1607,6 +1900,7 @@ code:
             SemanticComment::TrackCaller => Node::TrackCaller(inner.into()),
             SemanticComment::External => inner,
             SemanticComment::Deprecated(_) => inner,
+            SemanticComment::DeriveEq | SemanticComment::DeriveSerialize => inner,
             SemanticComment::Boo => {
                 self.add_error(span, "The compiler is scared!");
                 inner
@@ -1925,14 +2219,23 @@ code:
         Ok(root)
     }
     fn switch(&mut self, branches: Vec<Sp<Word>>, span: CodeSpan) -> UiuaResult<Node> {
-        let count = branches.len();
         // Compile branches
-        let mut br = EcoVec::with_capacity(count);
+        let mut br = EcoVec::with_capacity(branches.len());
+        for branch in branches {
+            let span = branch.span.clone();
+            br.push((self.word_sig(branch)?, span));
+        }
+        self.switch_nodes(br, span)
+    }
+    fn switch_nodes(
+        &mut self,
+        branches: EcoVec<(SigNode, CodeSpan)>,
+        span: CodeSpan,
+    ) -> UiuaResult<Node> {
+        let mut br = EcoVec::with_capacity(branches.len());
         let mut rigid_indices = Vec::new();
         let mut flex_indices = Vec::new();
-        for (i, branch) in branches.into_iter().enumerate() {
-            let span = branch.span.clone();
-            let SigNode { node, sig } = self.word_sig(branch)?;
+        for (i, (SigNode { node, sig }, span)) in branches.into_iter().enumerate() {
             let is_flex = node
                 .iter()
                 .rposition(|node| matches!(node, Node::Prim(Primitive::Assert, _)))
@@ -1999,21 +2302,31 @@ code:
     }
     fn handle_primitive_deprecation(&mut self, prim: Primitive, span: &CodeSpan) {
         if let Some(suggestion) = prim.deprecation_suggestion() {
-            if !self.deprecated_prim_errors.insert(prim) {
-                return;
-            }
             let suggestion = if suggestion.is_empty() {
                 String::new()
             } else {
                 format!(", {suggestion}")
             };
-            self.emit_diagnostic(
+            if self.edition >= UiuaEdition::Edition2025 {
+                self.add_error(
+                    span.clone(),
+                    format!(
+                        "{} was removed in the 2025 edition{suggestion}",
+                        prim.format()
+                    ),
+                );
+                return;
+            }
+            if !self.deprecated_prim_errors.insert(prim) {
+                return;
+            }
+            self.emit_warning(
                 format!(
                     "{} is deprecated and will be removed in a future version{}",
                     prim.format(),
                     suggestion
                 ),
-                DiagnosticKind::Warning,
+                DiagnosticCategory::Deprecated,
                 span.clone(),
             );
         }
@@ -2386,6 +2699,18 @@ impl Compiler {
             self.diagnostics.insert(diagnostic);
         }
     }
+    fn emit_warning(
+        &mut self,
+        message: impl Into<String>,
+        category: DiagnosticCategory,
+        span: impl Into<Span>,
+    ) {
+        if self.warnings_as_errors.contains(&category) {
+            self.add_error(span, message.into());
+        } else {
+            self.emit_diagnostic(message, DiagnosticKind::Warning, span);
+        }
+    }
     fn add_error(&mut self, span: impl Into<Span>, message: impl ToString) {
         let e = self.error(span, message);
         self.errors.push(e);
@@ -2399,6 +2724,18 @@ impl Compiler {
             self.add_error(span.clone(), message().to_string());
         }
     }
+    fn experimental_feature_error<S>(
+        &mut self,
+        feature: FeatureFlags,
+        span: &CodeSpan,
+        message: impl FnOnce() -> S,
+    ) where
+        S: fmt::Display,
+    {
+        if !self.feature_flags.contains(feature) {
+            self.experimental_error(span, message);
+        }
+    }
     fn experimental_error_it<S>(&mut self, span: &CodeSpan, thing: impl FnOnce() -> S)
     where
         S: fmt::Display,
@@ -2422,12 +2759,7 @@ impl Compiler {
             .any(|sc| sc.experimental || sc.experimental_error)
     }
     fn error(&self, span: impl Into<Span>, message: impl ToString) -> UiuaError {
-        UiuaErrorKind::Run {
-            message: span.into().sp(message.to_string()),
-            info: Vec::new(),
-            inputs: self.asm.inputs.clone().into(),
-        }
-        .into()
+        UiuaError::new(message, span).with_inputs(self.asm.inputs.clone())
     }
     fn error_with_info<S, M>(
         &self,
@@ -2460,7 +2792,7 @@ impl Compiler {
                     message.push('.');
                 }
             }
-            self.emit_diagnostic(message, DiagnosticKind::Warning, span.clone());
+            self.emit_warning(message, DiagnosticCategory::Deprecated, span.clone());
         }
         // Validate public
         if local.public {
@@ -2536,6 +2868,26 @@ impl Compiler {
         self.compile_bind_function(name, local, function, 0, meta)?;
         Ok(())
     }
+    /// Bind a constant value in the current scope
+    ///
+    /// Code compiled afterwards can refer to the constant by name.
+    pub fn set_global_constant(&mut self, name: impl Into<Ident>, val: Value) -> &mut Self {
+        let name = name.into();
+        let local = LocalName {
+            index: self.next_global,
+            public: true,
+        };
+        self.next_global += 1;
+        let span = CodeSpan::literal(name.clone());
+        self.asm.add_binding_at(
+            local,
+            BindingKind::Const(Some(val)),
+            Some(span),
+            BindingMeta::default(),
+        );
+        self.scope.names.insert(name, local);
+        self
+    }
     /// Create and bind a function in the current scope
     ///
     /// This function is the only way to bind `# External!` functions.
@@ -2562,6 +2914,540 @@ impl Compiler {
             self.bind_function_with_meta(name, function, meta)
         }
     }
+    /// Create and bind a modifier in the current scope
+    ///
+    /// The number of function arguments the modifier takes is determined by the `!`s at the end of its name.
+    /// `sig` receives the signatures of the operands and returns the signature of the modified function.
+    /// `f` receives the operands when the modified function is called.
+    ///
+    /// # Errors
+    /// Returns an error if the name is not a modifier name
+    pub fn create_bind_modifier(
+        &mut self,
+        name: impl Into<EcoString>,
+        sig: impl Fn(&[Signature]) -> Result<Signature, String> + SendSyncNative + 'static,
+        f: impl Fn(&[SigNode], &mut Uiua) -> UiuaResult + SendSyncNative + 'static,
+    ) -> UiuaResult {
+        let name = name.into();
+        let args = ident_modifier_args(&name);
+        if args == 0 {
+            return Err(UiuaError::new(
+                format!("`{name}` is not a modifier name because it does not end in `!`"),
+                Span::Builtin,
+            ));
+        }
+        let local = LocalName {
+            index: self.next_global,
+            public: true,
+        };
+        self.next_global += 1;
+        let meta = BindingMeta {
+            external: true,
+            ..Default::default()
+        };
+        let span = CodeSpan::literal(name.clone());
+        (self.asm).add_binding_at(local, BindingKind::IndexMacro(args), Some(span), meta);
+        self.scope.names.insert(name, local);
+        let modifier = DynamicModifier {
+            sig: Arc::new(sig),
+            f: Arc::new(f),
+        };
+        self.dynamic_modifiers.insert(local.index, modifier);
+        Ok(())
+    }
+    /// Register custom inverses for a function
+    ///
+    /// This is mainly useful for functions created with [`Compiler::create_function`],
+    /// which cannot otherwise be inverted.
+    /// The `un` inverse must have the inverse signature of the function.
+    /// The first `under` function is called before the inner function,
+    /// and the second is called after it.
+    ///
+    /// # Errors
+    /// Returns an error if the `un` inverse has the wrong signature
+    pub fn register_custom_inverse(
+        &mut self,
+        func: &Function,
+        un: Option<&Function>,
+        under: Option<(&Function, &Function)>,
+    ) -> UiuaResult {
+        if let Some(un) = un {
+            if un.sig != func.sig.inverse() {
+                return Err(self.error(
+                    Span::Builtin,
+                    format!(
+                        "un inverse of {} must have signature {}, but it has signature {}",
+                        func.id,
+                        func.sig.inverse(),
+                        un.sig
+                    ),
+                ));
+            }
+        }
+        let sig_node = |f: &Function| SigNode::new(f.sig, self.asm[f].clone());
+        let cust = CustomInverse {
+            normal: Ok(sig_node(func)),
+            un: un.map(sig_node),
+            under: under.map(|(before, after)| (sig_node(before), sig_node(after))),
+            is_obverse: true,
+            ..Default::default()
+        };
+        let span = self.add_span(Span::Builtin);
+        self.asm[func] = Node::CustomInverse(cust.into(), span);
+        Ok(())
+    }
+    /// Write the compiled assembly in an indented text format
+    ///
+    /// This includes all bindings, function bodies, and the root node.
+    /// Each line shows a single node with its span, if it has one.
+    pub fn dump_ir(&self, mut f: impl Write) -> UiuaResult {
+        fn line(s: &mut String, depth: usize, text: fmt::Arguments) {
+            s.push_str(&"  ".repeat(depth));
+            s.push_str(&text.to_string());
+            s.push('\n');
+        }
+        fn dump(node: &Node, asm: &Assembly, depth: usize, s: &mut String) {
+            let span = (node.span())
+                .and_then(|i| asm.spans.get(i))
+                .map(|span| format!(" @ {span}"))
+                .unwrap_or_default();
+            let sig_nodes = |label: &str, sns: &[SigNode], s: &mut String| {
+                for sn in sns {
+                    line(s, depth + 1, format_args!("{label} {}", sn.sig));
+                    dump(&sn.node, asm, depth + 2, s);
+                }
+            };
+            match node {
+                Node::Run(nodes) => nodes.iter().for_each(|node| dump(node, asm, depth, s)),
+                Node::Mod(prim, args, _) => {
+                    line(s, depth, format_args!("mod {prim}{span}"));
+                    sig_nodes("arg", args, s);
+                }
+                Node::ImplMod(prim, args, _) => {
+                    line(s, depth, format_args!("mod {prim}{span}"));
+                    sig_nodes("arg", args, s);
+                }
+                Node::Switch { branches, sig, .. } => {
+                    line(s, depth, format_args!("switch {sig}{span}"));
+                    sig_nodes("branch", branches, s);
+                }
+                Node::CustomInverse(cust, _) => {
+                    line(s, depth, format_args!("custom-inverse{span}"));
+                    if let Ok(normal) = &cust.normal {
+                        sig_nodes("normal", slice::from_ref(normal), s);
+                    }
+                    if let Some(un) = &cust.un {
+                        sig_nodes("un", slice::from_ref(un), s);
+                    }
+                    if let Some((before, after)) = &cust.under {
+                        sig_nodes("under-do", slice::from_ref(before), s);
+                        sig_nodes("under-undo", slice::from_ref(after), s);
+                    }
+                    if let Some(anti) = &cust.anti {
+                        sig_nodes("anti", slice::from_ref(anti), s);
+                    }
+                }
+                Node::Array { len, inner, .. } => {
+                    line(s, depth, format_args!("array {len:?}{span}"));
+                    dump(inner, asm, depth + 1, s);
+                }
+                Node::WithLocal { def, inner, .. } => {
+                    line(s, depth, format_args!("bind-local {def}{span}"));
+                    sig_nodes("inner", slice::from_ref(inner.as_ref()), s);
+                }
+                Node::Repeat { count, inner, .. } => {
+                    line(s, depth, format_args!("repeat {count}{span}"));
+                    sig_nodes("inner", slice::from_ref(inner.as_ref()), s);
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    let name = match node {
+                        Node::NoInline(_) => "no-inline",
+                        Node::TrackCaller(_) => "track-caller",
+                        _ => "commented-out",
+                    };
+                    line(s, depth, format_args!("{name}"));
+                    dump(inner, asm, depth + 1, s);
+                }
+                node => line(s, depth, format_args!("{node:?}{span}")),
+            }
+        }
+        let asm = &self.asm;
+        let mut s = String::new();
+        line(&mut s, 0, format_args!("bindings"));
+        for (i, binding) in asm.bindings.iter().enumerate() {
+            let name = (binding.span)
+                .try_as_str(&asm.inputs, |s| s.to_string())
+                .unwrap_or_default();
+            let kind = match &binding.kind {
+                BindingKind::Const(_) => "const".into(),
+                BindingKind::Func(f) => format!("func {} {}", f.index, f.sig),
+                BindingKind::Import(path) => format!("import {}", path.display()),
+                BindingKind::Module(_) => "module".into(),
+                BindingKind::Scope(_) => "scope".into(),
+                BindingKind::IndexMacro(n) => format!("index macro {n}"),
+                BindingKind::CodeMacro(_) => "code macro".into(),
+                BindingKind::Error => "error".into(),
+            };
+            let vis = if binding.public { "" } else { " private" };
+            line(&mut s, 1, format_args!("{i} {name}: {kind}{vis}"));
+        }
+        line(&mut s, 0, format_args!("functions"));
+        for (i, node) in asm.functions.iter().enumerate() {
+            match nodes_sig(node.as_slice()) {
+                Ok(sig) => line(&mut s, 1, format_args!("{i} {sig}")),
+                Err(_) => line(&mut s, 1, format_args!("{i}")),
+            }
+            dump(node, asm, 2, &mut s);
+        }
+        line(&mut s, 0, format_args!("root"));
+        dump(&asm.root, asm, 1, &mut s);
+        (f.write_all(s.as_bytes())).map_err(|e| UiuaError::new(e.to_string(), Span::Builtin))
+    }
+    /// Reconstruct a human-readable Uiua expression from a compiled [`Node`]
+    ///
+    /// The output is meant for inspecting compiler output and is not guaranteed to compile
+    pub fn format_node(node: &Node, asm: &Assembly) -> String {
+        let mut s = String::new();
+        node.write_source(asm, &mut s);
+        s
+    }
+    /// Expand a macro call and get the resulting code
+    ///
+    /// `args` are the macro's operands as Uiua code, such as `(+|×)`.
+    /// The expansion is formatted if possible.
+    /// The compiler's state is not changed.
+    pub fn macro_expand(&mut self, macro_name: &str, args: &str) -> UiuaResult<String> {
+        let backup = self.clone();
+        let res = self.load_str(&format!("{macro_name}{args}")).map(drop);
+        // The outermost expansion is the one that starts first and ends last
+        let expansion = (self.code_meta.macro_expansions.iter())
+            .filter(|(span, _)| !backup.code_meta.macro_expansions.contains_key(span))
+            .max_by_key(|(span, _)| (std::cmp::Reverse(span.start.char_pos), span.end.char_pos))
+            .map(|(_, (_, code))| code.clone());
+        *self = backup;
+        let Some(code) = expansion else {
+            res?;
+            return Err(UiuaError::new(
+                format!("`{macro_name}` is not a macro"),
+                Span::Builtin,
+            ));
+        };
+        Ok(match format_str(&code, &FormatConfig::default()) {
+            Ok(formatted) => formatted.output.trim_end().into(),
+            Err(_) => code,
+        })
+    }
+    /// Get the set of system functions used anywhere in an assembly
+    pub fn used_sys_ops(asm: &Assembly) -> HashSet<SysOp> {
+        fn collect(node: &Node, ops: &mut HashSet<SysOp>) {
+            match node {
+                Node::Prim(Primitive::Sys(op), _) => {
+                    ops.insert(*op);
+                }
+                Node::Mod(prim, args, _) => {
+                    if let Primitive::Sys(op) = prim {
+                        ops.insert(*op);
+                    }
+                    args.iter().for_each(|sn| collect(&sn.node, ops))
+                }
+                Node::ImplPrim(prim, _) => ops.extend(prim.sys_op()),
+                Node::Run(nodes) => nodes.iter().for_each(|node| collect(node, ops)),
+                Node::ImplMod(prim, args, _) => {
+                    ops.extend(prim.sys_op());
+                    args.iter().for_each(|sn| collect(&sn.node, ops))
+                }
+                Node::Switch { branches, .. } => {
+                    branches.iter().for_each(|sn| collect(&sn.node, ops))
+                }
+                Node::CustomInverse(cust, _) => cust.nodes().for_each(|sn| collect(&sn.node, ops)),
+                Node::Array { inner, .. } => collect(inner, ops),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    collect(&inner.node, ops)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) => collect(inner, ops),
+                _ => {}
+            }
+        }
+        let mut ops = HashSet::new();
+        collect(&asm.root, &mut ops);
+        for node in &asm.functions {
+            collect(node, &mut ops);
+        }
+        ops
+    }
+    /// Get the set of system functions that can possibly be called when running an assembly
+    ///
+    /// Unlike [`Compiler::used_sys_ops`], this only follows function calls reachable from the root.
+    /// If the program can call a dynamic function, every system function is considered reachable.
+    pub fn reachable_sys_ops(asm: &Assembly) -> HashSet<SysOp> {
+        fn collect(
+            node: &Node,
+            asm: &Assembly,
+            ops: &mut HashSet<SysOp>,
+            visited: &mut HashSet<usize>,
+        ) {
+            match node {
+                Node::Prim(Primitive::Sys(op), _) => {
+                    ops.insert(*op);
+                }
+                Node::Mod(prim, args, _) => {
+                    if let Primitive::Sys(op) = prim {
+                        ops.insert(*op);
+                    }
+                    args.iter()
+                        .for_each(|sn| collect(&sn.node, asm, ops, visited))
+                }
+                Node::Dynamic(_) => ops.extend(SysOp::ALL),
+                Node::Call(f, _) if visited.insert(f.index) => collect(&asm[f], asm, ops, visited),
+                Node::CallGlobal(index, _) | Node::CallMacro { index, .. } => {
+                    if let Some(BindingKind::Func(f)) = asm.bindings.get(*index).map(|b| &b.kind) {
+                        if visited.insert(f.index) {
+                            collect(&asm[f], asm, ops, visited)
+                        }
+                    }
+                }
+                Node::Run(nodes) => nodes
+                    .iter()
+                    .for_each(|node| collect(node, asm, ops, visited)),
+                Node::ImplPrim(prim, _) => ops.extend(prim.sys_op()),
+                Node::ImplMod(prim, args, _) => {
+                    ops.extend(prim.sys_op());
+                    args.iter()
+                        .for_each(|sn| collect(&sn.node, asm, ops, visited))
+                }
+                Node::Switch { branches, .. } => branches
+                    .iter()
+                    .for_each(|sn| collect(&sn.node, asm, ops, visited)),
+                Node::CustomInverse(cust, _) => cust
+                    .nodes()
+                    .for_each(|sn| collect(&sn.node, asm, ops, visited)),
+                Node::Array { inner, .. } => collect(inner, asm, ops, visited),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    collect(&inner.node, asm, ops, visited)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) => {
+                    collect(inner, asm, ops, visited)
+                }
+                _ => {}
+            }
+        }
+        let mut ops = HashSet::new();
+        collect(&asm.root, asm, &mut ops, &mut HashSet::new());
+        ops
+    }
+    /// Map every node in the assembly to its source span
+    ///
+    /// Nodes without a span are skipped.
+    pub fn source_map(&self) -> SourceMap {
+        fn collect(
+            node: &Node,
+            function: Option<usize>,
+            pos: &mut usize,
+            asm: &Assembly,
+            map: &mut SourceMap,
+        ) {
+            if let Node::Run(nodes) = node {
+                nodes
+                    .iter()
+                    .for_each(|node| collect(node, function, pos, asm, map));
+                return;
+            }
+            let node_ref = NodeRef {
+                function,
+                position: *pos,
+            };
+            *pos += 1;
+            if let Some(span) = node.span() {
+                map.push((node_ref, asm.spans[span].clone()));
+            }
+            match node {
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => args
+                    .iter()
+                    .for_each(|sn| collect(&sn.node, function, pos, asm, map)),
+                Node::Switch { branches, .. } => {
+                    (branches.iter()).for_each(|sn| collect(&sn.node, function, pos, asm, map))
+                }
+                Node::CustomInverse(cust, _) => {
+                    (cust.nodes()).for_each(|sn| collect(&sn.node, function, pos, asm, map))
+                }
+                Node::Array { inner, .. } => collect(inner, function, pos, asm, map),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    collect(&inner.node, function, pos, asm, map)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    collect(inner, function, pos, asm, map)
+                }
+                _ => {}
+            }
+        }
+        let asm = &self.asm;
+        let mut map = SourceMap::new();
+        collect(&asm.root, None, &mut 0, asm, &mut map);
+        for (i, node) in asm.functions.iter().enumerate() {
+            collect(node, Some(i), &mut 0, asm, &mut map);
+        }
+        map
+    }
+    /// Check that every function in the assembly has a body that matches its declared signature
+    ///
+    /// Functions whose body signature cannot be inferred are skipped.
+    pub fn check_signatures(&self) -> Vec<SignatureError> {
+        fn collect<'a>(node: &'a Node, asm: &'a Assembly, funcs: &mut IndexSet<&'a Function>) {
+            match node {
+                Node::Run(nodes) => nodes.iter().for_each(|node| collect(node, asm, funcs)),
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    args.iter().for_each(|sn| collect(&sn.node, asm, funcs))
+                }
+                Node::Call(f, _) if funcs.insert(f) => collect(&asm[f], asm, funcs),
+                Node::Switch { branches, .. } => {
+                    branches.iter().for_each(|sn| collect(&sn.node, asm, funcs))
+                }
+                Node::CustomInverse(cust, _) => {
+                    cust.nodes().for_each(|sn| collect(&sn.node, asm, funcs))
+                }
+                Node::Array { inner, .. } => collect(inner, asm, funcs),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    collect(&inner.node, asm, funcs)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) | Node::CommentedOut(inner) => {
+                    collect(inner, asm, funcs)
+                }
+                _ => {}
+            }
+        }
+        let asm = &self.asm;
+        let mut funcs = IndexSet::new();
+        for binding in &asm.bindings {
+            if let BindingKind::Func(f) = &binding.kind {
+                if funcs.insert(f) {
+                    collect(&asm[f], asm, &mut funcs);
+                }
+            }
+        }
+        collect(&asm.root, asm, &mut funcs);
+        (funcs.into_iter())
+            .filter_map(|f| {
+                let inferred = asm[f].sig().ok()?;
+                (inferred != f.sig).then(|| SignatureError {
+                    id: f.id.clone(),
+                    declared: f.sig,
+                    inferred,
+                })
+            })
+            .collect()
+    }
+    /// Describe the public bindings of the compiled code
+    ///
+    /// Modules and imports are described along with their public bindings.
+    /// Macros are not included.
+    /// Neither are constants whose values are not known until the assembly runs.
+    pub fn export_interface(&self) -> Vec<BindingInterface> {
+        self.names_interface(&self.scope.names)
+    }
+    fn names_interface(&self, names: &LocalNames) -> Vec<BindingInterface> {
+        (names.iter())
+            .filter(|(_, local)| local.public)
+            .filter_map(|(name, local)| {
+                let binding = self.asm.bindings.get(local.index)?;
+                let kind = match &binding.kind {
+                    BindingKind::Const(Some(val)) => {
+                        InterfaceKind::Constant(val.element_type(), val.shape().clone())
+                    }
+                    BindingKind::Func(f) => InterfaceKind::Function(f.sig),
+                    BindingKind::Module(module) => {
+                        InterfaceKind::Module(self.names_interface(&module.names))
+                    }
+                    BindingKind::Import(path) => {
+                        InterfaceKind::Module(self.names_interface(&self.imports.get(path)?.names))
+                    }
+                    _ => return None,
+                };
+                let doc = (binding.meta.comment.as_ref()).map(|comment| comment.text.to_string());
+                Some(BindingInterface {
+                    name: name.clone(),
+                    kind,
+                    doc,
+                })
+            })
+            .collect()
+    }
+    /// Inline calls to functions whose bodies have at most `max_nodes` nodes
+    ///
+    /// Recursive functions and functions marked `# No inline!` or `# Track caller!` are not inlined.
+    /// The inlined nodes keep their original spans.
+    pub fn inline_small_functions(&mut self, max_nodes: usize) {
+        fn node_count(node: &Node) -> usize {
+            match node {
+                Node::Run(nodes) => nodes.iter().map(node_count).sum(),
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    1 + args.iter().map(|sn| node_count(&sn.node)).sum::<usize>()
+                }
+                Node::Switch { branches, .. } => {
+                    1 + branches
+                        .iter()
+                        .map(|sn| node_count(&sn.node))
+                        .sum::<usize>()
+                }
+                Node::Array { inner, .. } => 1 + node_count(inner),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    1 + node_count(&inner.node)
+                }
+                _ => 1,
+            }
+        }
+        fn inline(node: &mut Node, small: &HashMap<usize, Node>) {
+            match node {
+                Node::Call(f, _) => {
+                    if let Some(body) = small.get(&f.index) {
+                        let mut body = body.clone();
+                        inline(&mut body, small);
+                        *node = body;
+                    }
+                }
+                Node::Run(nodes) => {
+                    *node = (take(nodes).into_iter())
+                        .map(|mut node| {
+                            inline(&mut node, small);
+                            node
+                        })
+                        .collect();
+                }
+                Node::Mod(_, args, _) | Node::ImplMod(_, args, _) => {
+                    for arg in args.make_mut() {
+                        inline(&mut arg.node, small);
+                    }
+                }
+                Node::Switch { branches, .. } => {
+                    for branch in branches.make_mut() {
+                        inline(&mut branch.node, small);
+                    }
+                }
+                Node::Array { inner, .. } => inline(Arc::make_mut(inner), small),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    inline(&mut Arc::make_mut(inner).node, small)
+                }
+                Node::NoInline(inner) | Node::TrackCaller(inner) => {
+                    inline(Arc::make_mut(inner), small)
+                }
+                _ => {}
+            }
+        }
+        let small: HashMap<usize, Node> = (self.asm.functions.iter().enumerate())
+            .filter(|(_, body)| {
+                !matches!(body, Node::NoInline(_) | Node::TrackCaller(_))
+                    && node_count(body) <= max_nodes
+                    && !body.is_recursive(&self.asm)
+            })
+            .map(|(i, body)| (i, body.clone()))
+            .collect();
+        if small.is_empty() {
+            return;
+        }
+        inline(&mut self.asm.root, &small);
+        for body in self.asm.functions.make_mut() {
+            inline(body, &small);
+        }
+    }
     fn sig_of(&self, node: &Node, span: &CodeSpan) -> UiuaResult<Signature> {
         node.sig().map_err(|e| {
             self.error(
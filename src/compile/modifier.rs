@@ -180,14 +180,14 @@ impl Compiler {
                         cust.normal = Ok(a.clone());
                         if !b.node.is_empty() {
                             if !a.sig.is_compatible_with(b.sig.inverse()) {
-                                self.emit_diagnostic(
+                                self.emit_warning(
                                     format!(
                                         "First and second functions must have \
                                         opposite signatures, \
                                         but their signatures are {} and {}",
                                         a.sig, b.sig
                                     ),
-                                    DiagnosticKind::Warning,
+                                    DiagnosticCategory::SignatureMismatch,
                                     modifier.span.clone(),
                                 );
                             }
@@ -206,14 +206,14 @@ impl Compiler {
                         cust.normal = Ok(a.clone());
                         if !b.node.is_empty() {
                             if !a.sig.is_compatible_with(b.sig.inverse()) {
-                                self.emit_diagnostic(
+                                self.emit_warning(
                                     format!(
                                         "First and second functions must have \
                                         opposite signatures, \
                                         but their signatures are {} and {}",
                                         a.sig, b.sig
                                     ),
-                                    DiagnosticKind::Warning,
+                                    DiagnosticCategory::SignatureMismatch,
                                     modifier.span.clone(),
                                 );
                             }
@@ -224,25 +224,25 @@ impl Compiler {
                         }
                         if !e.node.is_empty() {
                             match a.sig.anti() {
-                                None => self.emit_diagnostic(
+                                None => self.emit_warning(
                                     format!(
                                         "An anti inverse is specified, but the first \
                                         function's signature {} cannot have an \
                                         anti inverse",
                                         a.sig
                                     ),
-                                    DiagnosticKind::Warning,
+                                    DiagnosticCategory::SignatureMismatch,
                                     modifier.span.clone(),
                                 ),
                                 Some(sig) if sig != e.sig => {
-                                    self.emit_diagnostic(
+                                    self.emit_warning(
                                         format!(
                                             "The first function's signature implies an \
                                             anti inverse with signature {sig}, but the \
                                             fifth function's signature is {}",
                                             e.sig
                                         ),
-                                        DiagnosticKind::Warning,
+                                        DiagnosticCategory::SignatureMismatch,
                                         modifier.span.clone(),
                                     );
                                 }
@@ -332,6 +332,16 @@ impl Compiler {
         mut modified: Modified,
         subscript: Option<Sp<Subscript>>,
     ) -> UiuaResult<Node> {
+        // Built-in variant matching
+        if let Modifier::Ref(r) = &modified.modifier.value {
+            if r.path.is_empty()
+                && r.name.value == "Match!"
+                && self.find_name(&r.name.value, r.in_macro_arg).is_none()
+            {
+                return self.match_variant(modified);
+            }
+        }
+
         let mut op_count = modified.code_operands().count();
 
         // De-sugar function pack
@@ -365,9 +375,10 @@ impl Compiler {
             let strict_args = match &modified.modifier.value {
                 Modifier::Primitive(_) => true,
                 Modifier::Macro(..) => false,
-                Modifier::Ref(name) => self
-                    .ref_local(name)?
-                    .is_some_and(|(_, local)| self.index_macros.contains_key(&local.index)),
+                Modifier::Ref(name) => self.ref_local(name)?.is_some_and(|(_, local)| {
+                    self.index_macros.contains_key(&local.index)
+                        || self.dynamic_modifiers.contains_key(&local.index)
+                }),
             };
             if strict_args {
                 // Validate operand count
@@ -676,13 +687,13 @@ impl Compiler {
             prim @ (Above | Below) => {
                 let (mut sn, _) = self.monadic_modifier_op(modified)?;
                 if sn.sig.args < 2 {
-                    self.emit_diagnostic(
+                    self.emit_warning(
                         format!(
                             "The current behavior of {} with < 2 arguments \
                             is deprecated and will change in the future",
                             prim.format(),
                         ),
-                        DiagnosticKind::Warning,
+                        DiagnosticCategory::Deprecated,
                         modified.modifier.span.clone(),
                     );
                     sn.sig.args += 1;
@@ -1323,7 +1334,7 @@ impl Compiler {
         span: CodeSpan,
         operands: Vec<Sp<Word>>,
     ) -> UiuaResult<Node> {
-        self.experimental_error(&span, || {
+        self.experimental_feature_error(FeatureFlags::INLINE_MACROS, &span, || {
             "Inline macros are experimental. \
             To use them, add `# Experimental!` to the top of the file."
         });
@@ -1358,6 +1369,92 @@ impl Compiler {
             Node::Call(func, span)
         })
     }
+    /// Compile a `Match!` over data variants
+    ///
+    /// Each branch starts with the name of a variant.
+    /// The rest of the branch is called with the variant's fields.
+    fn match_variant(&mut self, modified: Modified) -> UiuaResult<Node> {
+        let span = modified.modifier.span.clone();
+        let branches: Vec<Sp<Func>> = match modified.operands.as_slice() {
+            [Sp {
+                value: Word::Pack(pack),
+                ..
+            }] => pack.lexical_order().cloned().collect(),
+            _ => (modified.code_operands().cloned())
+                .map(|word| match word.value {
+                    Word::Func(func) => word.span.sp(func),
+                    _ => word.span.clone().sp(Func {
+                        signature: None,
+                        lines: vec![vec![word]],
+                        closed: true,
+                    }),
+                })
+                .collect(),
+        };
+        let mut tagged: Vec<Option<(SigNode, CodeSpan)>> = Vec::new();
+        for branch in branches {
+            let mut func = branch.value;
+            let name_pos = (func.lines.iter().enumerate()).find_map(|(i, line)| {
+                (line.iter().position(|word| word.value.is_code())).map(|j| (i, j))
+            });
+            let Some((i, j)) = name_pos else {
+                return Err(self.error(branch.span, "Match! branches must start with a variant"));
+            };
+            let name = func.lines[i].remove(j);
+            let Word::Ref(r) = &name.value else {
+                return Err(self.error(name.span, "Match! branches must start with a variant"));
+            };
+            let tag = (self.ref_local(r)?)
+                .and_then(|(_, local)| self.variant_tags.get(&local.index).copied());
+            let Some(tag) = tag else {
+                return Err(self.error(
+                    name.span.clone(),
+                    format!("`{}` is not a data variant", r.name.value),
+                ));
+            };
+            if tagged.get(tag).is_some_and(Option::is_some) {
+                return Err(self.error(
+                    name.span.clone(),
+                    format!("Variant `{}` is matched more than once", r.name.value),
+                ));
+            }
+            // Un-construct the variant before running the rest of the branch
+            let name_span = name.span.clone();
+            let un = Word::Modified(Box::new(Modified {
+                modifier: name_span.clone().sp(Modifier::Primitive(Primitive::Un)),
+                operands: vec![name],
+                pack_expansion: false,
+            }));
+            func.lines.insert(0, vec![name_span.sp(un)]);
+            let sn = self.word_sig(branch.span.clone().sp(Word::Func(func)))?;
+            if tagged.len() <= tag {
+                tagged.resize(tag + 1, None);
+            }
+            tagged[tag] = Some((sn, branch.span));
+        }
+        let span_index = self.add_span(span.clone());
+        let branches = (tagged.into_iter())
+            .map(|branch| {
+                branch.unwrap_or_else(|| {
+                    let node = Node::from_iter([
+                        Node::new_push(0),
+                        Node::new_push("Unmatched variant"),
+                        Node::Prim(Primitive::Assert, span_index),
+                    ]);
+                    (SigNode::new((0, 0), node), span.clone())
+                })
+            })
+            .collect();
+        // Get the variant's tag
+        let mut node = Node::from_iter([
+            Node::Prim(Primitive::Dup, span_index),
+            Node::Prim(Primitive::Deshape, span_index),
+            Node::Prim(Primitive::First, span_index),
+            Node::ImplPrim(ImplPrimitive::UnBox, span_index),
+        ]);
+        node.push(self.switch_nodes(branches, span)?);
+        Ok(node)
+    }
     fn modifier_ref(
         &mut self,
         r: Ref,
@@ -1449,6 +1546,9 @@ impl Compiler {
         } else if let Some(mac) = self.code_macros.get(&local.index).cloned() {
             // Code macros
             self.code_macro(Some(r.name.value), modifier_span, operands, mac)?
+        } else if let Some(m) = self.dynamic_modifiers.get(&local.index).cloned() {
+            // Dynamic modifiers
+            self.dynamic_modifier(modifier_span, operands, m)?
         } else if let Some(m) =
             (self.asm.bindings.get(local.index)).and_then(|binfo| match &binfo.kind {
                 BindingKind::Module(m) => Some(m),
@@ -1469,6 +1569,19 @@ impl Compiler {
         self.comptime_depth -= 1;
         Ok(node)
     }
+    fn dynamic_modifier(
+        &mut self,
+        modifier_span: CodeSpan,
+        operands: Vec<Sp<Word>>,
+        m: DynamicModifier,
+    ) -> UiuaResult<Node> {
+        let ops = self.args(operands)?;
+        let sigs: Vec<Signature> = ops.iter().map(|sn| sn.sig).collect();
+        let sig = (m.sig)(&sigs).map_err(|e| self.error(modifier_span, e))?;
+        let f = m.f;
+        let df = self.create_dynamic_function(sig, move |env| f(&ops, env));
+        Ok(Node::Dynamic(df))
+    }
     fn code_macro(
         &mut self,
         mac_name: Option<Ident>,
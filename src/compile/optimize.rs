@@ -138,6 +138,7 @@ static OPTIMIZATIONS: &[&dyn Optimization] = &[
     &SplitByOpt,
     &AllSameOpt,
     &RepeatRandOpt,
+    &StaticRepeatOpt,
     &PopConst,
     &TraceOpt,
     &ValidateTypeOpt,
@@ -510,6 +511,54 @@ impl Optimization for RepeatRandOpt {
     }
 }
 
+/// The largest repetition count that is unrolled inline
+const MAX_UNROLL: usize = 8;
+
+#[derive(Debug)]
+struct StaticRepeatOpt;
+impl Optimization for StaticRepeatOpt {
+    fn match_and_replace(&self, nodes: &mut EcoVec<Node>) -> bool {
+        match_and_replace(nodes, |nodes| {
+            let [Push(n), Mod(Repeat, args, span), ..] = nodes else {
+                return None;
+            };
+            let [f] = args.as_slice() else {
+                return None;
+            };
+            // Functions that change the stack height are left to the modifier,
+            // which guards against creating too many values.
+            // Functions that touch the under stack are left alone
+            // so that pre-evaluation sees the whole loop.
+            if f.sig.args != f.sig.outputs || nodes_clean_sig(f.node.as_slice()).is_none() {
+                return None;
+            }
+            // Leave random repeats for `RepeatRandOpt`
+            if let [Prim(Rand, _), ..] = f.node.as_slice() {
+                return None;
+            }
+            let count = match n {
+                Value::Num(arr) if arr.rank() == 0 => arr.data[0],
+                Value::Byte(arr) if arr.rank() == 0 => arr.data[0] as f64,
+                _ => return None,
+            };
+            if count < 0.0 || count.fract() != 0.0 || count.is_infinite() {
+                return None;
+            }
+            let count = count as usize;
+            let new = if count <= MAX_UNROLL {
+                Node::from_iter(vec![f.node.clone(); count])
+            } else {
+                Node::Repeat {
+                    count,
+                    inner: f.clone().into(),
+                    span: *span,
+                }
+            };
+            Some((2, new))
+        })
+    }
+}
+
 opt!(
     TraceOpt,
     (
@@ -113,11 +113,33 @@ impl fmt::Display for UiuaError {
 }
 
 impl UiuaError {
+    /// Create an error with a message at a span
+    ///
+    /// This is useful for returning errors from code that does not have access to a [`Uiua`](crate::Uiua)
+    pub fn new(message: impl ToString, span: impl Into<Span>) -> Self {
+        UiuaErrorKind::Run {
+            message: span.into().sp(message.to_string()),
+            info: Vec::new(),
+            inputs: Default::default(),
+        }
+        .into()
+    }
     /// Attach some info to the error
     pub fn with_info(mut self, info: impl IntoIterator<Item = (String, Option<Span>)>) -> Self {
         self.infos.extend(info);
         self
     }
+    /// Attach a note without a span to the error
+    pub fn with_note(self, note: impl ToString) -> Self {
+        self.with_info([(note.to_string(), None)])
+    }
+    /// Set the inputs used to display the error's spans
+    pub(crate) fn with_inputs(mut self, new_inputs: Inputs) -> Self {
+        if let UiuaErrorKind::Run { inputs, .. } = &mut self.kind {
+            **inputs = new_inputs;
+        }
+        self
+    }
     /// Get the value of the error if it was thrown by `assert`
     pub fn value(self) -> Value {
         match self.kind {
@@ -393,6 +415,20 @@ pub enum DiagnosticKind {
     Warning,
 }
 
+/// Categories of warnings
+///
+/// See [`Compiler::with_warning_as_error`](crate::Compiler::with_warning_as_error)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum DiagnosticCategory {
+    /// Use of a deprecated primitive, binding, or behavior
+    Deprecated,
+    /// A declared signature does not match the code
+    SignatureMismatch,
+    /// A macro's name does not match its placeholders
+    MacroName,
+}
+
 impl fmt::Display for Diagnostic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.message.fmt(f)
@@ -7,34 +7,42 @@ pub struct Fill<'a> {
     other_error: &'static str,
 }
 
+/// A value set by `fill`
 #[derive(Debug, Clone, Copy)]
 pub struct FillValue<T = Value> {
+    /// The fill value
     pub value: T,
+    /// The side the fill applies to, if any
     pub side: Option<SubSide>,
 }
 
 impl<T> FillValue<T> {
+    /// Create a new fill value
     pub fn new(val: impl Into<T>, side: impl Into<Option<SubSide>>) -> Self {
         Self {
             value: val.into(),
             side: side.into(),
         }
     }
+    /// Try to convert the fill value
     pub fn try_map<U, E>(&self, f: impl FnOnce(&T) -> Result<U, E>) -> Result<FillValue<U>, E> {
         Ok(FillValue {
             value: f(&self.value)?,
             side: self.side,
         })
     }
+    /// Convert a reference to the fill value
     pub fn map_ref<'a, U>(&'a self, f: impl FnOnce(&'a T) -> U) -> FillValue<U> {
         FillValue {
             value: f(&self.value),
             side: self.side,
         }
     }
+    /// Check if the fill only applies to the left side
     pub fn is_left(&self) -> bool {
         self.side == Some(SubSide::Left)
     }
+    /// Check if the fill only applies to the right side
     pub fn is_right(&self) -> bool {
         self.side == Some(SubSide::Right)
     }
@@ -1393,7 +1393,7 @@ impl Formatter<'_> {
             });
             env.rt.backend.set_output_enabled(enabled);
 
-            let mut values = env.rt.output_comments;
+            let mut values = env.take_output_comments();
             if let Err(e) = res {
                 let next = (0..).take_while(|i| values.contains_key(i)).count();
                 values.insert(next, vec![vec![e.to_string().into()]]);
@@ -857,6 +857,10 @@ pub enum SemanticComment {
     External,
     /// Mark a function as deprecated
     Deprecated(EcoString),
+    /// Generate an equality function for a data definition
+    DeriveEq,
+    /// Generate JSON conversion functions for a data definition
+    DeriveSerialize,
     #[doc(hidden)]
     Boo,
 }
@@ -872,6 +876,8 @@ impl fmt::Display for SemanticComment {
             SemanticComment::External => write!(f, "# External!"),
             SemanticComment::Deprecated(s) if s.is_empty() => write!(f, "# Deprecated!"),
             SemanticComment::Deprecated(s) => write!(f, "# Deprecated! {s}"),
+            SemanticComment::DeriveEq => write!(f, "# Eq!"),
+            SemanticComment::DeriveSerialize => write!(f, "# Serialize!"),
             SemanticComment::Boo => write!(f, "# Boo!"),
         }
     }
@@ -1145,6 +1151,8 @@ impl<'a> Lexer<'a> {
                             "No inline!" => self.end(NoInline, start),
                             "Track caller!" => self.end(TrackCaller, start),
                             "External!" => self.end(External, start),
+                            "Eq!" => self.end(DeriveEq, start),
+                            "Serialize!" => self.end(DeriveSerialize, start),
                             "Boo!" => self.end(Boo, start),
                             s => {
                                 if let Some(suf) = s.strip_prefix("Deprecated!") {
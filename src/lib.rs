@@ -130,9 +130,13 @@ The `uiua` crate has the following noteable feature flags:
 - `native_sys`: Enables the [`NativeSys`] backend. This is the default backend used by the interpreter.
 - `audio`: Enables audio features in the [`NativeSys`] backend.
 - `https`: Enables the `&httpsw` system function
+- `http`: Enables the `&http` system function in the [`NativeSys`] backend
 - `invoke`: Enables the `&invk` system function
 - `trash`: Enables the `&ftr` system function
 - `raw_mode`: Enables the `&raw` system function
+- `polars`: Enables conversion between [`Value`]s and Polars series
+- `arrow`: Enables conversion between [`Value`]s and Apache Arrow arrays
+- `wasm_export`: Enables the [`wasm_export`] module for exporting assemblies as WASM modules
 */
 
 #![allow(
@@ -145,6 +149,8 @@ The `uiua` crate has the following noteable feature flags:
 
 mod algorithm;
 mod array;
+#[cfg(feature = "arrow")]
+mod arrow;
 mod assembly;
 pub mod ast;
 mod boxed;
@@ -162,11 +168,14 @@ mod grid_fmt;
 mod lex;
 pub mod lsp;
 mod parse;
+#[cfg(feature = "polars")]
+mod polars;
 mod primitive;
 #[doc(hidden)]
 pub mod profile;
 mod run;
 mod shape;
+pub mod sparse;
 #[cfg(feature = "stand")]
 #[doc(hidden)]
 pub mod stand;
@@ -174,13 +183,15 @@ mod sys;
 mod tree;
 mod types;
 mod value;
+#[cfg(feature = "wasm_export")]
+pub mod wasm_export;
 #[cfg(feature = "window")]
 #[doc(hidden)]
 pub mod window;
 
 #[allow(unused_imports)]
 pub use self::{
-    algorithm::encode,
+    algorithm::{broadcast_shape, encode},
     array::*,
     assembly::*,
     boxed::*,
@@ -189,6 +200,7 @@ pub use self::{
     constant::*,
     error::*,
     ffi::*,
+    fill::FillValue,
     function::*,
     lex::is_ident_char,
     lex::*,
@@ -214,9 +226,16 @@ pub type Ident = EcoString;
 
 #[cfg(test)]
 mod tests {
-    use std::path::*;
+    use std::{
+        path::*,
+        sync::{Arc, Mutex},
+    };
 
-    use crate::{Compiler, Uiua};
+    use crate::{
+        ast, broadcast_shape, Array, Compiler, Complex, DiagnosticCategory, ElementType, ExecScope,
+        FeatureFlags, FunctionId, IntegrityError, LogLevel, Node, PreEvalMode, Primitive, RunMode,
+        SafeSys, Shape, Span, StackEvent, SysOp, Uiua, UiuaEdition, UiuaError, Value,
+    };
 
     fn test_files(filter: impl Fn(&Path) -> bool) -> impl Iterator<Item = PathBuf> {
         std::fs::read_dir("tests")
@@ -257,6 +276,9 @@ mod tests {
                 {
                     panic!("Test failed in {}:\n{}", path.display(), diag.report());
                 }
+                if let Some(e) = comp.check_signatures().first() {
+                    panic!("Signature error in {}: {e}", path.display());
+                }
                 let (stack, under_stack) = env.take_stacks();
                 if !stack.is_empty() {
                     panic!("{} had a non-empty stack", path.display());
@@ -409,6 +431,1349 @@ mod tests {
         });
     }
 
+    #[test]
+    fn run_str_batch() {
+        let mut env = Uiua::with_safe_sys();
+        let results = env.run_str_batch(&["X ← 5\n1 2", "+1 X", "Y"]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().len(), 2);
+        let vals = results[1].as_ref().unwrap();
+        assert_eq!(vals.len(), 1);
+        assert_eq!(vals[0], 6);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn import_hook() {
+        let mut env = Uiua::with_safe_sys()
+            .with_import_hook(|path| (path == "virtual").then(|| "X ← 5".into()));
+        env.run_str("~ \"virtual\" ~ X\nX").unwrap();
+        assert_eq!(env.pop_num().unwrap(), 5.0);
+        assert!(env.run_str("~ \"missing\" ~ X").is_err());
+    }
+
+    #[test]
+    fn import_resolver() {
+        let mut comp = Compiler::new().with_import_resolver(|importer, path| match path {
+            "a" => Ok("~ \"b\" ~ Y\nX ← +1 Y".into()),
+            "b" if importer.ends_with("a") => Ok("Y ← 5".into()),
+            _ => Err(UiuaError::new(
+                format!("Unknown module {path}"),
+                Span::Builtin,
+            )),
+        });
+        comp.load_str("~ \"a\" ~ X\nX").unwrap();
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(comp.finish()).unwrap();
+        assert_eq!(env.pop_num().unwrap(), 6.0);
+        let mut comp = Compiler::new().with_import_resolver(|_, path| {
+            Err(UiuaError::new(
+                format!("Unknown module {path}"),
+                Span::Builtin,
+            ))
+        });
+        assert!(comp.load_str("~ \"b\" ~ Y").is_err());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn import_resolver_with_hook() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        static HOOK_CALLED: AtomicBool = AtomicBool::new(false);
+        let hook = |path: &str| {
+            HOOK_CALLED.store(true, Ordering::Relaxed);
+            (path == "a").then(|| "X ← 1".into())
+        };
+        let resolver = |_: &Path, path: &str| match path {
+            "a" => Ok("X ← 2".into()),
+            _ => Err(UiuaError::new(
+                format!("Unknown module {path}"),
+                Span::Builtin,
+            )),
+        };
+        // The resolver handles every import, no matter which was set first
+        let comps = [
+            Compiler::new()
+                .with_import_hook(hook)
+                .with_import_resolver(resolver),
+            Compiler::new()
+                .with_import_resolver(resolver)
+                .with_import_hook(hook),
+        ];
+        for mut comp in comps {
+            comp.load_str("~ \"a\" ~ X\nX").unwrap();
+            let mut env = Uiua::with_safe_sys();
+            env.run_asm(comp.finish()).unwrap();
+            assert_eq!(env.pop_num().unwrap(), 2.0);
+            // Resolver errors do not fall back to the hook
+            assert!(comp.load_str("~ \"b\" ~ Y").is_err());
+        }
+        assert!(!HOOK_CALLED.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn call_depth_limit() {
+        let code = "F ← |1 ⨬(∘|F -1)⊸>0\nF 5";
+        let limits = [(crate::FunctionId::Named("F".into()), 3)].into();
+        let mut env = Uiua::with_safe_sys().with_call_depth_limit(limits);
+        assert!(env.run_str(code).is_err());
+        // The limits survive the reset after an error
+        assert!(env.run_str(code).is_err());
+        Uiua::with_safe_sys().run_str(code).unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn dry_run() {
+        let mut env = Uiua::with_safe_sys();
+        env.compile_run(|comp| comp.mode(RunMode::Dry).load_str("1 2 3"))
+            .unwrap();
+        assert!(env.stack().is_empty());
+        assert!(env
+            .compile_run(|comp| comp.mode(RunMode::Dry).load_str("+ X"))
+            .is_err());
+        // Other entry points respect dry runs too
+        let mut comp = Compiler::new();
+        comp.mode(RunMode::Dry).load_str("&p 1 2 3").unwrap();
+        env.run_compiler(&mut comp).unwrap();
+        assert!(env.stack().is_empty());
+        let asm = comp.load_str("4").unwrap().finish();
+        env.run_asm(asm).unwrap();
+        assert!(env.stack().is_empty());
+        let stdout = env.downcast_backend::<SafeSys>().unwrap().take_stdout();
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn map_rows() {
+        let mut env = Uiua::with_safe_sys();
+        let val = Value::from([[1, 2], [3, 4]]);
+        let mapped = (val.clone())
+            .map_rows(|row, _| Ok(row.row_count().into()), &mut env)
+            .unwrap();
+        assert_eq!(mapped, Value::from([2, 2]));
+        let res = val.map_rows(
+            |row, _| {
+                Ok(if row == Value::from([1, 2]) {
+                    row
+                } else {
+                    1.into()
+                })
+            },
+            &mut env,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn call_returning() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("F ← ⊃+-\nG ← ⍤\"Nope\" 0").unwrap();
+        let funcs = env.bound_functions();
+        env.push(5);
+        let outputs = (env.call_returning(&funcs["F"], [3.into(), 1.into()])).unwrap();
+        assert_eq!(outputs, [Value::from(2), Value::from(4)]);
+        assert!(env.call_returning(&funcs["G"], [1.into()]).is_err());
+        assert_eq!(env.take_stack(), [Value::from(5)]);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn zip_rows() {
+        let mut env = Uiua::with_safe_sys();
+        let add = |a: Value, b: Value, env: &mut Uiua| a.add(b, env);
+        let zipped = Value::from([1, 2, 3]).zip_rows(Value::from([4, 5, 6]), add, &mut env);
+        assert_eq!(zipped.unwrap(), Value::from([5, 7, 9]));
+        let res = Value::from([1, 2]).zip_rows(Value::from([4, 5, 6]), add, &mut env);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn memo_limit() {
+        let mut env = Uiua::with_safe_sys().with_memo_limit(2);
+        env.run_str("F ← memo(+1)").unwrap();
+        let f = env.bound_functions().remove("F").unwrap();
+        for n in [1, 2, 1, 3] {
+            let outputs = env.call_returning(&f, [n.into()]).unwrap();
+            assert_eq!(outputs, [Value::from(n + 1)]);
+        }
+        let mut memo = env.rt.memo.get_or_default().borrow_mut();
+        assert_eq!(memo.values().map(|f_memo| f_memo.len()).sum::<usize>(), 2);
+        // 2 was the least recently used
+        let f_memo = memo.values_mut().next().unwrap();
+        assert!(f_memo.get(&[1.into()], None).is_some());
+        assert!(f_memo.get(&[2.into()], None).is_none());
+    }
+
+    #[test]
+    fn rename_binding() {
+        let mut asm = (Compiler::new().load_str("X ← &rs 1 0\nF ← +X\nG ← ×X"))
+            .unwrap()
+            .finish();
+        let res = asm.rename_binding("X", "Y").unwrap();
+        assert_eq!(res.call_sites, 2);
+        let name = asm.bindings[res.index]
+            .span
+            .as_str(&asm.inputs, |s| s.to_string());
+        assert_eq!(name, "Y");
+        assert!(asm.rename_binding("X", "Z").is_err());
+        assert!(asm.rename_binding("F", "G").is_err());
+
+        let mut asm = (Compiler::new().load_str("F ← +1\nG ← F F\nH ← ⊃F∘"))
+            .unwrap()
+            .finish();
+        let res = asm.rename_binding("F", "Inc").unwrap();
+        assert_eq!(res.call_sites, 3);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn create_function() {
+        let mut env = Uiua::with_safe_sys();
+        let f = env.create_function((1, 1), |env| {
+            let n = env.pop_num()?;
+            env.push(n * 2.0);
+            Ok(())
+        });
+        let outputs = env.call_returning(&f, [3.into()]).unwrap();
+        assert_eq!(outputs, [Value::from(6)]);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn reduce() {
+        let rows = Value::from([[1, 2], [3, 4], [5, 6]]).reduce(Vec::new(), |mut acc, row| {
+            acc.push(row);
+            Ok(acc)
+        });
+        assert_eq!(rows.unwrap(), [[1, 2], [3, 4], [5, 6]].map(Value::from));
+        let count = Value::from(5).reduce(0, |acc, _| Ok(acc + 1));
+        assert_eq!(count.unwrap(), 1);
+    }
+
+    #[test]
+    fn thread_count() {
+        let env = Uiua::with_safe_sys().with_thread_count(2);
+        assert_eq!(env.thread_count(), 2);
+    }
+
+    #[test]
+    fn inline_small_functions() {
+        let mut comp = Compiler::new();
+        comp.load_str("F ← +1\nG ← ×2 F").unwrap();
+        comp.inline_small_functions(3);
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(comp.finish()).unwrap();
+        let g = env.bound_functions().remove("G").unwrap();
+        let body = env.asm[&g].as_slice();
+        assert!(!body.iter().any(|node| matches!(node, Node::Call(..))));
+        let outputs = env.call_returning(&g, [5.into()]).unwrap();
+        assert_eq!(outputs, [Value::from(12)]);
+    }
+
+    #[test]
+    fn from_iter_2d() {
+        let env = Uiua::with_safe_sys();
+        let rows = (0..3).map(|i| (0..2).map(move |j| i * 2 + j));
+        let val = Value::from_iter_2d(rows, &env).unwrap();
+        assert_eq!(val, Value::from([[0, 1], [2, 3], [4, 5]]));
+        let ragged = Value::from_iter_2d([vec![1, 2], vec![3]], &env);
+        assert!(ragged.is_err());
+        // Rows are validated before they are fully collected
+        let rows: [Box<dyn Iterator<Item = i32>>; 2] =
+            [Box::new([1, 2].into_iter()), Box::new(std::iter::repeat(0))];
+        assert!(Value::from_iter_2d(rows, &env).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn error_builder() {
+        let mut comp = Compiler::new();
+        comp.create_bind_function("F", (0, 0), |_| {
+            Err(UiuaError::new("Oh no", Span::Builtin).with_info([("It broke".into(), None)]))
+        })
+        .unwrap();
+        comp.load_str("F").unwrap();
+        let err = Uiua::with_safe_sys().run_compiler(&mut comp).unwrap_err();
+        assert!(err.to_string().ends_with("Oh no"));
+        assert_eq!(err.infos[0].0, "It broke");
+        let err = UiuaError::new("Oh no", Span::Builtin)
+            .with_note("It broke")
+            .with_note(format_args!("Try {}", 2));
+        assert_eq!(
+            err.infos,
+            [("It broke".into(), None), ("Try 2".into(), None)]
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let val = Value::from([[1, 2], [3, 4]]).flatten();
+        assert_eq!(val, Value::from([1, 2, 3, 4]));
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("{[1 2] {3 [4_5 6_7]}}").unwrap();
+        let flat = env.pop(0).unwrap().flatten_boxed(&env).unwrap();
+        assert_eq!(flat, Value::from([1, 2, 3, 4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn save_state() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("F ← +1\nF 5 {\"a\" [1 2]}").unwrap();
+        let f = env.bound_functions().remove("F").unwrap();
+        let bytes = env.save_state().unwrap();
+        let mut loaded = Uiua::load_state(&bytes).unwrap();
+        assert_eq!(loaded.stack(), env.stack());
+        let outputs = loaded.call_returning(&f, [2.into()]).unwrap();
+        assert_eq!(outputs, [Value::from(3)]);
+    }
+
+    #[test]
+    fn used_sys_ops() {
+        let asm = (Compiler::new().load_str("F ← &p\nG ← &sl\n&pf 1"))
+            .unwrap()
+            .finish();
+        let ops = Compiler::used_sys_ops(&asm);
+        assert_eq!(ops.len(), 3);
+        assert!([SysOp::Print, SysOp::Sleep, SysOp::Prin]
+            .iter()
+            .all(|op| ops.contains(op)));
+    }
+
+    #[test]
+    fn check_signatures() {
+        // Methods that update a field take the instance from below their arguments
+        let code = "# Experimental!\n┌─╴Foo\n  ~ {Bar Baz}\n  ~IncrBar ← Self⍜Bar+₁\n└─╴";
+        let mut comp = Compiler::new();
+        comp.load_str(code).unwrap();
+        assert!(comp.check_signatures().is_empty());
+    }
+
+    #[test]
+    fn derive_eq() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("# Eq!\n~Pt {X Y}\nPt 1 2").unwrap();
+        let instance = env.pop("instance").unwrap();
+        assert_eq!(instance.meta().label.as_deref(), Some("Pt"));
+        for code in ["# Eq!\nF ← +1", "# Eq!\n~Pt", "# Eq!\n+1 2", "# Eq!"] {
+            assert!(Uiua::with_safe_sys().run_str(code).is_err(), "{code}");
+        }
+    }
+
+    #[test]
+    fn derive_serialize() {
+        let code = "# Serialize!\n~Foo {A|B ← +}";
+        let Err(err) = Uiua::with_safe_sys().run_str(code) else {
+            panic!("serialization with a 2-argument initializer compiled");
+        };
+        assert!(err.to_string().contains("initializer takes 2 arguments"));
+    }
+
+    #[test]
+    fn format_node() {
+        let mut comp = Compiler::new();
+        comp.pre_eval_mode(PreEvalMode::Lazy);
+        let asm = comp
+            .load_str("F ← ⊂1\nG ← ≡(+1×2) F\n⇌G [2 3]")
+            .unwrap()
+            .finish();
+        let crate::BindingKind::Func(f) = &asm.bindings[1].kind else {
+            panic!("G is not a function");
+        };
+        assert_eq!(Compiler::format_node(&asm[f], &asm), "≡(+ 1 × 2) F");
+        assert_eq!(Compiler::format_node(&asm.root, &asm), "⇌ G [2 3]");
+    }
+
+    #[test]
+    fn reachable_sys_ops() {
+        let asm = (Compiler::new().load_str("F ← &p\nG ← &sl\nG 0\n&pf 1"))
+            .unwrap()
+            .finish();
+        let ops = Compiler::reachable_sys_ops(&asm);
+        assert_eq!(ops, [SysOp::Sleep, SysOp::Prin].into_iter().collect());
+        // Inverted system functions still use the backend
+        let asm = (Compiler::new().load_str("F ← °&clip\nF \"hi\""))
+            .unwrap()
+            .finish();
+        assert!(Compiler::used_sys_ops(&asm).contains(&SysOp::Clip));
+        assert!(Compiler::reachable_sys_ops(&asm).contains(&SysOp::Clip));
+    }
+
+    #[test]
+    fn type_coerce() {
+        let env = Uiua::with_safe_sys();
+        let coerce = |vals: Vec<Value>| Value::type_coerce(vals, &env).unwrap();
+        let val = coerce(vec!["ab".into(), Value::from([99.0, 100.0])]);
+        assert_eq!(val, Value::from([['a', 'b'], ['c', 'd']]));
+        let val = coerce(vec!['a'.into(), Value::from(-1.0)]);
+        assert_eq!(val.element_type(), ElementType::Box);
+        let val = coerce(vec![1.0.into(), Complex::new(0.0, 1.0).into()]);
+        assert_eq!(val.element_type(), ElementType::Complex);
+    }
+
+    #[test]
+    fn element_type_order() {
+        use ElementType as T;
+        let mut types = [T::Box, T::Char, T::Complex, T::Num];
+        types.sort();
+        assert_eq!(types, [T::Num, T::Complex, T::Char, T::Box]);
+        assert!(Value::from(Complex::new(0.0, 1.0)) < Value::from('a'));
+    }
+
+    #[test]
+    fn exec_in_scope() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("F ← ↙5").unwrap();
+        let f = env.bound_functions().remove("F").unwrap();
+        let node = env.asm.sig_node(&f);
+        env.push([1, 2]);
+        env.exec_in_scope(ExecScope::new(node.clone()).fill(0))
+            .unwrap();
+        assert_eq!(env.pop(0).unwrap(), Value::from([1, 2, 0, 0, 0]));
+        assert!(env.fill_stack().is_empty());
+        env.push([1, 2]);
+        assert!(env.exec_in_scope(ExecScope::new(node)).is_err());
+    }
+
+    #[test]
+    fn output_limit() {
+        let mut env = Uiua::with_safe_sys().with_output_limit(2);
+        env.run_str("1 2").unwrap();
+        env.take_stack();
+        let err = env.run_str("1 2 3").err().unwrap();
+        assert!(err.to_string().contains("produced 3 values"));
+        assert_eq!(env.stack().len(), 2);
+    }
+
+    #[test]
+    fn source_map() {
+        let mut comp = Compiler::new();
+        comp.load_str("F ← +1").unwrap();
+        let map = comp.source_map();
+        let (node_ref, span) = map.iter().find(|(r, _)| r.function.is_some()).unwrap();
+        assert_eq!(node_ref.position, 1);
+        let Span::Code(span) = span else {
+            panic!("expected code span")
+        };
+        assert_eq!(span.as_str(&comp.assembly().inputs, str::to_string), "+");
+    }
+
+    #[test]
+    fn sample() {
+        use rand::{rngs::SmallRng, SeedableRng};
+        let mut rng = SmallRng::seed_from_u64(0);
+        let val = Value::from([1, 2, 3, 4, 5]);
+        let mut sampled = val.sample(5, &mut rng).unwrap();
+        sampled.sort_up();
+        assert_eq!(sampled, val);
+        assert!(val.sample(6, &mut rng).is_err());
+        let weights = Value::from([0.0, 0.0, 1.0, 0.0, 0.0]);
+        let sampled = val.sample_weighted(&weights, 1, &mut rng).unwrap();
+        assert_eq!(sampled, Value::from([3]));
+    }
+
+    #[test]
+    fn commented_out() {
+        let mut env = Uiua::with_safe_sys();
+        let node = Node::CommentedOut(Node::Prim(Primitive::Pop, 0).into());
+        assert_eq!(node.sig().unwrap(), (0, 0));
+        assert_eq!(Compiler::format_node(&node, &env.asm), "# ◌\n");
+        env.push(1);
+        env.exec_in_scope(ExecScope::new(node)).unwrap();
+        assert_eq!(env.stack(), [Value::from(1)]);
+        // Repeat without a count
+        let repeat = Node::Mod(
+            Primitive::Repeat,
+            [crate::SigNode::new((0, 1), Node::new_push(1))].into(),
+            0,
+        );
+        assert!(Node::CommentedOut(repeat.into()).sig().is_err());
+    }
+
+    #[test]
+    fn broadcast_shapes() {
+        let shape = |dims: &[usize]| Shape::from(dims);
+        let res = broadcast_shape(&shape(&[2, 1]), &shape(&[2, 3, 4])).unwrap();
+        assert_eq!(res, shape(&[2, 3, 4]));
+        assert!(broadcast_shape(&shape(&[2]), &shape(&[3])).is_err());
+    }
+
+    #[test]
+    fn macro_expand() {
+        let mut comp = Compiler::new();
+        comp.load_str("Twice! ← ^0^0\nRev! ←^ ⇌\nF ← +").unwrap();
+        assert_eq!(comp.macro_expand("Twice!", "(+1)").unwrap(), "+1+1");
+        assert_eq!(comp.macro_expand("Rev!", "(1|2)").unwrap(), "2 1");
+        assert!(comp.macro_expand("F", "").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "native_sys")]
+    fn cache_dir() {
+        let dir = std::env::temp_dir().join("uiua_cache_dir_test");
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("main.ua");
+        let lib = dir.join("lib.ua");
+        std::fs::write(&path, "~ \"lib.ua\" ~ X\n+ 1 X").unwrap();
+        std::fs::write(&lib, "X ← 2").unwrap();
+        let cache = dir.join("cache");
+        for _ in 0..2 {
+            let mut env = Uiua::with_native_sys().with_cache_dir(cache.clone());
+            env.run_file(&path).unwrap();
+            assert_eq!(env.pop_int().unwrap(), 3);
+        }
+        assert_eq!(std::fs::read_dir(&cache).unwrap().count(), 1);
+        // Changing an imported file invalidates the cache
+        std::fs::write(&lib, "X ← 5").unwrap();
+        let mut env = Uiua::with_native_sys().with_cache_dir(cache.clone());
+        env.run_file(&path).unwrap();
+        assert_eq!(env.pop_int().unwrap(), 6);
+        // Without file access, nothing is cached
+        let other = dir.join("other.ua");
+        std::fs::write(&other, "+ 1 2").unwrap();
+        let other_cache = dir.join("other_cache");
+        let mut env = Uiua::with_safe_sys().with_cache_dir(other_cache.clone());
+        env.run_file(&other).unwrap();
+        assert!(!other_cache.exists());
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn register_custom_inverse() {
+        let mut comp = Compiler::new();
+        let double = comp.create_function((1, 1), |env| {
+            let x = env.pop_num()?;
+            env.push(x * 2.0);
+            Ok(())
+        });
+        let halve = comp.create_function((1, 1), |env| {
+            let x = env.pop_num()?;
+            env.push(x / 2.0);
+            Ok(())
+        });
+        let bad = comp.create_function((2, 1), |_| Ok(()));
+        assert!(comp
+            .register_custom_inverse(&double, Some(&bad), None)
+            .is_err());
+        comp.register_custom_inverse(&double, Some(&halve), None)
+            .unwrap();
+        comp.bind_function("Double", double).unwrap();
+        comp.load_str("°Double 8").unwrap();
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(comp.finish()).unwrap();
+        assert_eq!(env.pop_num().unwrap(), 4.0);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn chunks() {
+        let mut env = Uiua::with_safe_sys();
+        let val = Value::from(Array::<f64>::new(5, [1.0, 2.0, 3.0, 4.0, 5.0]));
+        assert!(val.clone().chunks(2, &env).is_err());
+        let chunked = env
+            .with_fill(0.into(), None, |env| val.chunks(2, env))
+            .unwrap();
+        let expected = Array::<f64>::new([3, 2], [1.0, 2.0, 3.0, 4.0, 5.0, 0.0]);
+        assert_eq!(chunked, Value::from(expected));
+        // Filling to a huge chunk size errors instead of overflowing
+        let val = Value::from(Array::<f64>::new(5, [1.0, 2.0, 3.0, 4.0, 5.0]));
+        assert!(env
+            .with_fill(0.into(), None, |env| val.chunks(usize::MAX, env))
+            .is_err());
+    }
+
+    #[test]
+    fn stack_hook() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let hook_events = events.clone();
+        let mut env = Uiua::with_safe_sys().with_stack_hook(move |event| {
+            hook_events.lock().unwrap().push(match event {
+                StackEvent::Push(val) => format!("push {val}"),
+                StackEvent::Pop(val) => format!("pop {val}"),
+            })
+        });
+        env.push(1);
+        env.pop(()).unwrap();
+        assert_eq!(*events.lock().unwrap(), ["push 1", "pop 1"]);
+    }
+
+    #[test]
+    fn constant_fold() {
+        let mut comp = Compiler::new();
+        comp.pre_eval_mode(PreEvalMode::Lazy);
+        let mut asm = comp.load_str("F ← ×2\n+1 2 F 5").unwrap().finish();
+        asm.constant_fold();
+        assert!(asm.root.iter().any(|node| *node == Node::Push(3.into())));
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(asm).unwrap();
+        assert_eq!(env.pop_int().unwrap(), 3);
+        assert_eq!(env.pop_int().unwrap(), 10);
+        // Modifier bodies are not folded
+        let code = "⬚0(+↯[5][1 2]) 0";
+        let mut comp = Compiler::new();
+        comp.pre_eval_mode(PreEvalMode::Lazy);
+        let mut asm = comp.load_str(code).unwrap().finish();
+        asm.constant_fold();
+        let mut env = Uiua::with_safe_sys();
+        env.run_asm(asm).unwrap();
+        assert_eq!(env.pop(1).unwrap(), Value::from([1, 2, 0, 0, 0]));
+        // Large values are not inlined
+        let mut asm = comp.load_str("⇡1e5").unwrap().finish();
+        asm.constant_fold();
+        assert!((asm.root.iter()).all(|node| !matches!(node, Node::Push(val) if val.rank() > 0)));
+    }
+
+    #[test]
+    fn execution_elapsed() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("/+⇡1000").unwrap();
+        let elapsed = env.execution_elapsed();
+        assert!(elapsed < std::time::Duration::from_secs(60));
+        assert!(env.execution_elapsed() >= elapsed);
+    }
+
+    #[test]
+    fn dump_ir() {
+        let mut comp = Compiler::new();
+        comp.pre_eval_mode(PreEvalMode::Lazy);
+        comp.load_str("F ← +1\nF 2").unwrap();
+        let mut ir = Vec::new();
+        comp.dump_ir(&mut ir).unwrap();
+        let ir = String::from_utf8(ir).unwrap();
+        assert!(ir.starts_with("bindings\n"));
+        assert!(ir.contains("F: func"));
+        assert!(ir.contains("\nroot\n"));
+    }
+
+    #[test]
+    fn argmax_argmin() {
+        let env = Uiua::with_safe_sys();
+        let list = Value::from(Array::<f64>::new(4, [3.0, 7.0, 1.0, 7.0]));
+        assert_eq!(list.argmax(&env).unwrap(), Value::from(1.0));
+        assert_eq!(list.argmin(&env).unwrap(), Value::from(2.0));
+        let table = Value::from(Array::<f64>::new([2, 3], [1.0, 5.0, 2.0, 9.0, 0.0, 4.0]));
+        let expected = Array::<f64>::new(2, [1.0, 0.0]);
+        assert_eq!(table.argmax(&env).unwrap(), Value::from(expected));
+        let expected = Array::<f64>::new(2, [0.0, 1.0]);
+        assert_eq!(table.argmin(&env).unwrap(), Value::from(expected));
+    }
+
+    #[test]
+    fn from_bytes() {
+        assert_eq!(Value::from_bytes_auto(b"hi"), Value::from("hi"));
+        let invalid = [0xff, 0x00];
+        assert_eq!(
+            Value::from_bytes_auto(&invalid),
+            Value::from(invalid.to_vec())
+        );
+        assert_eq!(Value::from_bytes_raw(b"hi"), Value::from(b"hi".to_vec()));
+    }
+
+    #[test]
+    fn feature_flags() {
+        let code = "~F {A B} +A B\nF 1 2";
+        assert!(Compiler::new().load_str(code).is_err());
+        let mut comp = Compiler::new().with_feature_flags(FeatureFlags::DATA_FUNCTIONS);
+        comp.load_str(code).unwrap();
+    }
+
+    #[test]
+    fn value_inspector() {
+        let mut env = Uiua::with_safe_sys().with_value_inspector(|val| {
+            if val.as_char_array().is_some() {
+                return Err(UiuaError::new("Strings are not allowed", Span::Builtin));
+            }
+            if let Value::Num(arr) = val {
+                arr.data
+                    .as_mut_slice()
+                    .iter_mut()
+                    .for_each(|n| *n = n.abs());
+            }
+            Ok(())
+        });
+        env.run_str("¯3").unwrap();
+        assert_eq!(env.pop_num().unwrap(), 3.0);
+        let err = env.run_str("\"secret\"").err().unwrap();
+        assert!(err.to_string().contains("Strings are not allowed"));
+        assert!(env.stack().is_empty());
+        // A value rejected outside of execution does not fail the next run
+        env.push("secret");
+        assert!(env.stack().is_empty());
+        env.run_str("5").unwrap();
+        assert_eq!(env.pop_num().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn call_stack_snapshot() {
+        let env = Uiua::with_safe_sys();
+        let frames = env.call_stack_snapshot();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].id, Some(FunctionId::Main));
+    }
+
+    #[test]
+    fn table() {
+        let env = Uiua::with_safe_sys();
+        let data = Value::from(Array::<f64>::new([2, 2], [1.0, 2.0, 3.0, 4.0]));
+        let columns = Value::from(["a", "b"].as_slice());
+        let table = data.as_table(&columns, &env).unwrap();
+        let expected = Array::<f64>::new(2, [2.0, 4.0]);
+        assert_eq!(table.get_column("b").unwrap(), Value::from(expected));
+        assert!(table.get_column("c").is_err());
+        assert_eq!(table.to_value().1, columns);
+    }
+
+    #[test]
+    fn primitive_histogram() {
+        let mut env = Uiua::with_safe_sys().with_primitive_profiling();
+        env.push(3);
+        env.run_str("F ← +1\nF F ⇡").unwrap();
+        let histogram = env.primitive_histogram();
+        assert_eq!(histogram.get(&Primitive::Add), Some(&2));
+        assert_eq!(histogram.get(&Primitive::Range), Some(&1));
+        assert!(Uiua::with_safe_sys().primitive_histogram().is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        let env = Uiua::with_safe_sys();
+        let list = Value::from(Array::<f64>::new(4, [1.0, 4.0, 9.0, 16.0]));
+        let expected = Array::<f64>::new(3, [3.0, 5.0, 7.0]);
+        assert_eq!(
+            list.clone().diff(0, 1, &env).unwrap(),
+            Value::from(expected)
+        );
+        let expected = Array::<f64>::new(2, [2.0, 2.0]);
+        assert_eq!(list.diff(0, 2, &env).unwrap(), Value::from(expected));
+        let table = Value::from(Array::<f64>::new([2, 3], [1.0, 2.0, 4.0, 0.0, 5.0, 5.0]));
+        let expected = Array::<f64>::new([2, 2], [1.0, 2.0, 5.0, 0.0]);
+        assert_eq!(
+            table.clone().diff(1, 1, &env).unwrap(),
+            Value::from(expected)
+        );
+        assert!(table.diff(2, 1, &env).is_err());
+    }
+
+    #[test]
+    fn set_global_constant() {
+        let mut comp = Compiler::new();
+        comp.set_global_constant("Scale", 2.5.into());
+        comp.load_str("×Scale 4").unwrap();
+        let mut env = Uiua::with_safe_sys();
+        env.run_compiler(&mut comp).unwrap();
+        assert_eq!(env.pop_num().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn private_methods() {
+        let def = "# Experimental!\n\
+            ┌─╴Foo\n  ~ {A B}\n  ~Prod ↚ ×A B\n  Twice ← ×2 Prod\n└─╴\n";
+        let mut env = Uiua::with_safe_sys();
+        env.run_str(&format!("{def}Foo~Twice Foo~New 2 3")).unwrap();
+        assert_eq!(env.pop_num().unwrap(), 12.0);
+        let err = Compiler::new()
+            .load_str(&format!("{def}Foo~Prod Foo~New 2 3"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`Prod` is private"));
+    }
+
+    #[test]
+    fn verify_integrity() {
+        let mut comp = Compiler::new();
+        comp.load_str("F ← +1\nG ← ⊃(F|×2)\nG 5").unwrap();
+        let mut asm = comp.finish();
+        assert_eq!(asm.verify_integrity(), Vec::new());
+        asm.root.push(Node::CallGlobal(999, (0, 1).into()));
+        assert_eq!(
+            asm.verify_integrity(),
+            vec![IntegrityError::InvalidGlobal(999)]
+        );
+        // A bad function is reported once, not once per call
+        let mut asm = Compiler::new().load_str("1").unwrap().finish();
+        let node = Node::Prim(Primitive::Neg, 0);
+        let f = asm.add_function(FunctionId::Named("F".into()), (2, 1).into(), node);
+        asm.root.push(Node::Call(f.clone(), 0));
+        asm.root.push(Node::Call(f, 0));
+        let errors = asm.verify_integrity();
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(matches!(
+            errors[0],
+            IntegrityError::SignatureMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn log_level() {
+        let run = |level| {
+            let mut env = Uiua::with_safe_sys().with_log_level(level);
+            env.push(2);
+            env.run_str("+1").unwrap();
+            let stdout = env.downcast_backend::<SafeSys>().unwrap().take_stdout();
+            String::from_utf8(stdout).unwrap()
+        };
+        assert!(run(LogLevel::Info).is_empty());
+        let debug = run(LogLevel::Debug);
+        assert!(debug.contains('⏲') && !debug.contains('⋯'));
+        let trace = run(LogLevel::Trace);
+        assert!(trace.contains('⏲') && trace.contains('⋯'));
+    }
+
+    #[test]
+    fn log_level_reports() {
+        use crate::{DiagnosticKind, Report, ReportKind};
+        let run = |level| {
+            let mut env = Uiua::with_safe_sys().with_log_level(level);
+            env.rt.reports.push(Report::new(ReportKind::Error, "bad"));
+            (env.rt.reports).push(Report::new(DiagnosticKind::Warning.into(), "careful"));
+            (env.rt.reports).push(Report::new(DiagnosticKind::Info.into(), "fyi"));
+            env.print_reports();
+            let stdout = env.downcast_backend::<SafeSys>().unwrap().take_stdout();
+            let stdout = String::from_utf8(stdout).unwrap();
+            ["bad", "careful", "fyi"].map(|s| stdout.contains(s))
+        };
+        assert_eq!(run(LogLevel::Silent), [false, false, false]);
+        assert_eq!(run(LogLevel::Error), [true, false, false]);
+        assert_eq!(run(LogLevel::Warn), [true, true, false]);
+        assert_eq!(run(LogLevel::Info), [true, true, true]);
+    }
+
+    #[test]
+    #[cfg(feature = "num-complex")]
+    fn complex_slice() {
+        use num_complex::Complex64;
+        let data = [Complex64::new(1.0, 2.0), Complex64::new(3.0, -4.0)];
+        let val = Value::from_complex_slice(&data, [2]);
+        assert_eq!(
+            val.as_complex_array().unwrap().data[1],
+            Complex::new(3.0, -4.0)
+        );
+        assert_eq!(val.as_complex_slice().unwrap(), data);
+        assert!(Value::from(5).as_complex_slice().is_none());
+    }
+
+    #[test]
+    fn outer_product() {
+        let mut env = Uiua::with_safe_sys();
+        let a = Value::from([1, 2]);
+        let b = Value::from([[10, 20], [30, 40]]);
+        let table = Value::outer_product(a, b, |a, b, env| a.add(b, env), &mut env).unwrap();
+        let expected = Array::new([2, 2, 2], [11.0, 21.0, 31.0, 41.0, 12.0, 22.0, 32.0, 42.0]);
+        assert_eq!(table, Value::from(expected));
+        let pairs = Value::outer_product(
+            Value::from([1, 2, 3]),
+            Value::from([4, 5]),
+            |a, b, env| Value::from_row_values(vec![a, b], env),
+            &mut env,
+        )
+        .unwrap();
+        assert_eq!(pairs.shape().dims(), [3, 2, 2]);
+    }
+
+    #[test]
+    fn take_output_comments() {
+        let mut env = Uiua::with_safe_sys();
+        env.run_str("1 2\n## \n3\n## \n").unwrap();
+        assert_eq!(env.output_comment_count(), 2);
+        let comments = env.take_output_comments();
+        assert_eq!(comments[&1], [vec![Value::from(3)]]);
+        assert_eq!(env.output_comment_count(), 0);
+    }
+
+    #[test]
+    fn on_error() {
+        use std::sync::{Arc, Mutex};
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors2 = errors.clone();
+        let mut env = Uiua::with_safe_sys().with_on_error(move |e, env| {
+            (errors2.lock().unwrap()).push((e.to_string(), env.stack().len()))
+        });
+        env.push(7);
+        env.push(0);
+        assert!(env.run_str("F ← ⍤\"boom\"\n[F]").is_err());
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.contains("boom"));
+        assert_eq!(errors[0].1, 1);
+    }
+
+    #[test]
+    fn extract_function() {
+        let asm = (Compiler::new().load_str("A ← 3\nSq ← ×.\nF ← +A Sq\nG ← 10\nF 2 G"))
+            .unwrap()
+            .finish();
+        let extracted = asm.extract_function("F").unwrap();
+        assert!(extracted.bindings.len() < asm.bindings.len());
+        assert_eq!(extracted.verify_integrity(), Vec::new());
+        let mut env = Uiua::with_safe_sys();
+        env.push(2);
+        env.run_asm(extracted).unwrap();
+        assert_eq!(env.take_stack(), [Value::from(7)]);
+        assert!(asm.extract_function("A").is_err());
+        assert!(asm.extract_function("H").is_err());
+    }
+
+    #[test]
+    fn parse_only() {
+        let mut comp = Compiler::new();
+        let items = comp.parse_only("F ← +1\nF 2").unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], ast::Item::Binding(_)));
+        assert!(comp.asm.bindings.is_empty());
+        assert!(comp.parse_only("(").is_err());
+    }
+
+    #[test]
+    fn peek_stack() {
+        let mut env = Uiua::with_safe_sys();
+        assert!(env.peek_top().is_err());
+        env.push(1);
+        env.push(2);
+        env.push(3);
+        assert_eq!(env.peek_stack(2).unwrap(), [Value::from(2), Value::from(3)]);
+        assert_eq!(*env.peek_top().unwrap(), Value::from(3));
+        assert!(env.peek_stack(4).is_err());
+        assert_eq!(env.stack().len(), 3);
+    }
+
+    #[test]
+    fn edition() {
+        let code = ", 1 2";
+        assert!(Compiler::new().load_str(code).is_ok());
+        let e = (Compiler::new().set_edition(UiuaEdition::Edition2025))
+            .load_str(code)
+            .err()
+            .unwrap();
+        assert!(e.to_string().contains("removed in the 2025 edition"));
+    }
+
+    #[test]
+    fn sparse() {
+        use crate::sparse::SparseValue;
+        let val = Value::from([[0.0, 3.0, 0.0], [0.0, 0.0, 0.0], [-2.0, 0.0, 0.5]]);
+        let sparse = val.to_sparse(1.0).unwrap();
+        assert_eq!(sparse.indices(), [vec![0, 1], vec![2, 0]]);
+        assert_eq!(sparse.data(), [3.0, -2.0]);
+        let other = Value::from([[1, -3, 0], [0, 0, 0], [4, 0, 0]])
+            .to_sparse(0.0)
+            .unwrap();
+        let sum = Value::from_sparse(sparse.add(&other).unwrap()).unwrap();
+        assert_eq!(sum, Value::from([[1, 0, 0], [0, 0, 0], [2, 0, 0]]));
+        let product = Value::from_sparse(sparse.mul(&other).unwrap()).unwrap();
+        assert_eq!(product, Value::from([[0, -9, 0], [0, 0, 0], [-8, 0, 0]]));
+        assert!(Value::from("abc").to_sparse(0.0).is_err());
+        // Elements are sorted, and bad indices are rejected
+        let shape = Shape::from([2, 2]);
+        let unsorted =
+            SparseValue::new(shape.clone(), vec![vec![1, 1], vec![0, 1]], vec![1.0, 2.0]);
+        assert_eq!(unsorted.unwrap().indices(), [vec![0, 1], vec![1, 1]]);
+        assert!(SparseValue::new(shape.clone(), vec![vec![2, 0]], vec![1.0]).is_err());
+        let repeated = SparseValue::new(shape.clone(), vec![vec![0, 0]; 2], vec![1.0, 2.0]);
+        assert!(repeated.is_err());
+        // Multiplying by an unstored zero is not skipped
+        let inf = SparseValue::new(shape.clone(), vec![vec![0, 0]], vec![f64::INFINITY]).unwrap();
+        let zero = SparseValue::new(shape, Vec::new(), Vec::new()).unwrap();
+        assert!(inf.mul(&zero).unwrap().data()[0].is_nan());
+        let huge = SparseValue::new([usize::MAX / 2, 4].into(), Vec::new(), Vec::new()).unwrap();
+        assert!(Value::from_sparse(huge).is_err());
+    }
+
+    #[test]
+    fn dynamic_modifier() {
+        let mut comp = Compiler::new();
+        comp.create_bind_modifier(
+            "Twice!",
+            |sigs| match sigs {
+                [sig] if sig.args == sig.outputs => Ok(*sig),
+                _ => Err("Twice!'s function must not change the stack height".into()),
+            },
+            |ops, env| {
+                env.exec(ops[0].clone())?;
+                env.exec(ops[0].clone())
+            },
+        )
+        .unwrap();
+        assert!(comp
+            .create_bind_modifier("Twice", |_| unreachable!(), |_, _| Ok(()))
+            .is_err());
+        comp.load_str("Twice!(+1) 5").unwrap();
+        let mut env = Uiua::with_safe_sys();
+        env.run_compiler(&mut comp).unwrap();
+        assert_eq!(env.take_stack(), [Value::from(7)]);
+        assert!(comp.load_str("Twice!(1)").is_err());
+    }
+
+    #[test]
+    fn call_graph() {
+        let asm = (Compiler::new())
+            .load_str("F ← +1\nG ← F ×2\nH ← G F\nR ← |1 ⨬(∘|R -1) >0.\nH R 3")
+            .unwrap()
+            .finish();
+        let graph = asm.call_graph();
+        assert_eq!(graph.callees(1), [0]);
+        assert_eq!(graph.callees(2), [0, 1]);
+        assert!(graph.is_recursive(3));
+        assert!(!graph.is_recursive(2));
+        assert_eq!(graph.topological_order(), None);
+        let mut graph = graph;
+        graph.calls.remove(&3);
+        assert_eq!(graph.topological_order(), Some(vec![2, 1, 0]));
+    }
+
+    #[test]
+    fn io_rate_limit() {
+        let mut env = Uiua::with_safe_sys().with_io_rate_limit(2);
+        env.run_str("&p 1 &p 2 &p 3").unwrap();
+        env.run_str("&fe \"a.txt\" &fe \"b.txt\"").unwrap();
+        let e = env.run_str("&fe \"a.txt\"").err().unwrap();
+        assert!(e.to_string().contains("rate limit"), "{e}");
+        // Commands are limited too
+        let e = env.run_str("&runi {\"ls\"}").err().unwrap();
+        assert!(e.to_string().contains("rate limit"), "{e}");
+        // Backends that cannot sleep are still throttled rather than failing
+        let mut env = Uiua::with_safe_sys().with_io_rate_limit(20);
+        let start = std::time::Instant::now();
+        env.run_str("⍥(&fe \"a.txt\")25").unwrap();
+        assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    }
+
+    #[test]
+    fn warning_as_error() {
+        let mut comp = Compiler::new().with_warning_as_error(DiagnosticCategory::SignatureMismatch);
+        assert!(comp.load_str(", 1 2").is_ok());
+        let mut comp = Compiler::new().with_warning_as_error(DiagnosticCategory::Deprecated);
+        let e = comp.load_str(", 1 2").err().unwrap();
+        assert!(e.to_string().contains("deprecated"), "{e}");
+    }
+
+    #[test]
+    fn scatter_add() {
+        let env = Uiua::with_safe_sys();
+        let target = Value::from([0, 0, 0, 0, 0]);
+        let indices = Value::from([[1], [3], [-4]]);
+        let summed = Value::scatter_add(target.clone(), &indices, &[1, 2, 3].into(), &env);
+        assert_eq!(summed.unwrap(), Value::from([0, 4, 0, 2, 0]));
+        let target = Value::from([[0.5, 0.0], [0.0, 0.0]]);
+        let indices = Value::from([[0, 0], [1, 1], [0, 0]]);
+        let summed = Value::scatter_add(target, &indices, &[1, 2, 3].into(), &env);
+        assert_eq!(summed.unwrap(), Value::from([[4.5, 0.0], [0.0, 2.0]]));
+        let indices = Value::from([[5]]);
+        assert!(Value::scatter_add(Value::from([0, 0]), &indices, &[1].into(), &env).is_err());
+        // Boolean targets stop being boolean
+        let mut target = Value::from([0u8, 1]);
+        target.meta_mut().flags |= crate::ArrayFlags::BOOLEAN;
+        let summed = Value::scatter_add(target, &Value::from([[1]]), &[2].into(), &env).unwrap();
+        assert!(!summed.meta().flags.is_boolean());
+    }
+
+    #[test]
+    fn exec_all() {
+        let mut env = Uiua::with_safe_sys();
+        env.push(1);
+        let nodes = [
+            Node::new_push(2),
+            Node::Prim(Primitive::Add, 0),
+            Node::Prim(Primitive::Add, 0),
+            Node::Prim(Primitive::Dup, 0),
+        ];
+        let results = env.exec_all(&nodes);
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok() && results[1].is_ok() && results[3].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(env.take_stack(), [Value::from(3), Value::from(3)]);
+    }
+
+    #[test]
+    fn correlate() {
+        let env = Uiua::with_safe_sys();
+        let a = Value::from([1, 2, 3]);
+        let b = Value::from([0.0, 1.0, 0.5]);
+        let Value::Num(corr) = Value::correlate(&a, &b, &env).unwrap() else {
+            panic!("correlation should be numbers")
+        };
+        assert_eq!(corr.shape, [5]);
+        for (c, expected) in corr.data.iter().zip([0.5, 2.0, 3.5, 3.0, 0.0]) {
+            assert!((c - expected).abs() < 1e-9, "{c} != {expected}");
+        }
+        assert!(Value::correlate(&a, &Value::from("abc"), &env).is_err());
+    }
+
+    #[test]
+    fn run_length_encoding() {
+        let env = Uiua::with_safe_sys();
+        let val = Value::from("aaabccdddd");
+        let (values, lengths) = Value::encode_rle(val.clone(), &env).unwrap();
+        assert_eq!(values, Value::from("abcd"));
+        assert_eq!(lengths, Value::from([3, 1, 2, 4]));
+        assert_eq!(Value::decode_rle(values, lengths, &env).unwrap(), val);
+        assert!(Value::encode_rle(Value::from([[1, 2], [3, 4]]), &env).is_err());
+        let bad_lengths = Value::from([1, 2]);
+        assert!(Value::decode_rle(Value::from("abc"), bad_lengths, &env).is_err());
+    }
+
+    #[test]
+    fn matmul() {
+        let env = Uiua::with_safe_sys();
+        let a = Value::from([[1, 2, 3], [4, 5, 6]]);
+        let b = Value::from([[1.0, 0.0], [0.0, 1.0], [2.0, 2.0]]);
+        let prod = Value::matmul(a.clone(), b.clone(), &env).unwrap();
+        assert_eq!(prod, Value::from([[7, 8], [16, 17]]));
+        assert!(Value::matmul(a.clone(), a, &env).is_err());
+        assert!(Value::matmul(Value::from("ab"), b.clone(), &env).is_err());
+        // Larger matrices skip the small path
+        let big: Value =
+            Array::new([5, 3], (1..=15).map(f64::from).collect::<ecow::EcoVec<_>>()).into();
+        let prod = Value::matmul(big, b.clone(), &env).unwrap();
+        assert_eq!(
+            prod,
+            Value::from([[7, 8], [16, 17], [25, 26], [34, 35], [43, 44]])
+        );
+        // The top of the stack is the left matrix
+        let mut env = Uiua::with_safe_sys();
+        env.push(b);
+        env.push(Value::from([[1, 2, 3], [4, 5, 6]]));
+        let node = Node::ImplPrim(crate::ImplPrimitive::MatMul, 0);
+        env.exec_in_scope(ExecScope::new(node)).unwrap();
+        assert_eq!(env.take_stack(), [Value::from([[7, 8], [16, 17]])]);
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn value_transformer() {
+        let mut env = Uiua::with_safe_sys().with_value_transformer(|val| match val {
+            Value::Byte(arr) => Value::Num(arr.convert()),
+            val => val,
+        });
+        env.push(Value::Byte(Array::from_iter([1u8, 0])));
+        env.push("a");
+        let [a, b] = <[Value; 2]>::try_from(env.take_stack()).unwrap();
+        assert!(matches!(a, Value::Num(_)));
+        assert!(matches!(b, Value::Char(_)));
+        // Transformers and inspectors compose in the order they were added
+        let mut env = Uiua::with_safe_sys()
+            .with_value_transformer(|val| match val {
+                Value::Byte(arr) => Value::Num(arr.convert()),
+                val => val,
+            })
+            .with_value_inspector(|val| match val {
+                Value::Byte(_) => Err(UiuaError::new("bytes were not converted", Span::Builtin)),
+                _ => Ok(()),
+            })
+            .with_value_transformer(|val| crate::Boxed(val).into());
+        env.push(Value::Byte(Array::from_iter([1u8, 0])));
+        let val = env.pop("value").unwrap();
+        assert!(matches!(&val, Value::Box(arr) if matches!(arr.data[0].0, Value::Num(_))));
+    }
+
+    #[test]
+    fn export_interface() {
+        use crate::{BindingInterface, InterfaceKind};
+        let mut comp = Compiler::new();
+        comp.load_str(
+            "# The answer
+X ← 42
+F ← +1
+G ↚ ×2
+┌─╴M
+  Y ← \"hi\"
+└─╴",
+        )
+        .unwrap();
+        let interface = comp.export_interface();
+        let names: Vec<_> = interface.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["X", "F", "M"]);
+        assert_eq!(
+            interface[0],
+            BindingInterface {
+                name: "X".into(),
+                kind: InterfaceKind::Constant(ElementType::Num, Shape::SCALAR),
+                doc: Some("The answer".into()),
+            }
+        );
+        assert_eq!(interface[1].kind, InterfaceKind::Function((1, 1).into()));
+        let InterfaceKind::Module(module) = &interface[2].kind else {
+            panic!("M should be a module")
+        };
+        assert_eq!(
+            module[0].kind,
+            InterfaceKind::Constant(ElementType::Char, [2].into())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "native_sys")]
+    fn sleep_precise() {
+        let mut env = Uiua::with_native_sys();
+        let start = std::time::Instant::now();
+        env.run_str("&slp 0.005").unwrap();
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed >= std::time::Duration::from_millis(5),
+            "{elapsed:?}"
+        );
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "native_sys"))]
+    fn process_exit_code() {
+        let mut env = Uiua::with_native_sys();
+        env.run_str(r#"&runp {"sh" "-c" "exit 3"} """#).unwrap();
+        assert_eq!(env.pop_int().unwrap(), 3);
+        // A process killed by a signal does not report success
+        env.run_str(r#"&runp {"sh" "-c" "kill -9 $$"} """#).unwrap();
+        assert_eq!(env.pop_int().unwrap(), 128 + 9);
+    }
+
+    #[test]
+    #[cfg(feature = "native_sys")]
+    fn chroot() {
+        let dir = std::env::temp_dir().join(format!("uiua_chroot_test_{}", std::process::id()));
+        let (root, outside) = (dir.join("root"), dir.join("outside"));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        let mut env = Uiua::with_native_sys().with_chroot(root.clone());
+        // Absolute paths are relative to the root
+        env.run_str("&fwa \"/abs.txt\" \"hi\"").unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("abs.txt")).unwrap(), "hi");
+        // Parent directories
+        assert!(env.run_str("&fras \"../outside/secret.txt\"").is_err());
+        // Symlinks
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+            std::os::unix::fs::symlink(outside.join("new.txt"), root.join("dangling")).unwrap();
+            assert!(env.run_str("&fras \"link/secret.txt\"").is_err());
+            assert!(env.run_str("&fwa \"link/new.txt\" \"x\"").is_err());
+            assert!(env.run_str("&fwa \"dangling\" \"x\"").is_err());
+            assert!(!outside.join("new.txt").exists());
+        }
+        // Unconfined capabilities
+        assert!(env.run_str("&tcpc \"localhost:80\"").is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "arrow")]
+    fn arrow() {
+        use arrow_array::{FixedSizeListArray, Int32Array, StringArray};
+        for val in [
+            Value::from([1.0, 2.5, -3.0]),
+            Value::from([[1, 2], [3, 4], [5, 6]]),
+        ] {
+            let arrow = val.to_arrow().unwrap();
+            assert_eq!(Value::from_arrow(arrow.as_ref()).unwrap(), val);
+        }
+        let cube: Value = Array::new([1, 1, 1], [1.0]).into();
+        assert!(cube.to_arrow().is_err());
+        let boxes: Value = Array::from_iter(["ab", "cd"].map(|s| crate::Boxed(s.into()))).into();
+        let strings = boxes.to_arrow().unwrap();
+        assert_eq!(Value::from_arrow(strings.as_ref()).unwrap(), boxes);
+        // Nulls become NaN in numbers but are rejected in strings
+        let ints = Int32Array::from(vec![Some(1), None]);
+        let val = Value::from_arrow(&ints).unwrap();
+        assert!(matches!(&val, Value::Num(arr) if arr.data[1].is_nan()));
+        let strings = StringArray::from(vec![Some("a"), None]);
+        assert!(Value::from_arrow(&strings).is_err());
+        let lists = FixedSizeListArray::from_iter_primitive::<arrow_array::types::Int32Type, _, _>(
+            [Some(vec![Some(1), Some(2)]), None],
+            2,
+        );
+        assert!(Value::from_arrow(&lists).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "polars")]
+    fn polars() {
+        use ::polars::prelude::{NamedFrom, Series};
+        let val = Value::from([1.0, 2.5, -3.0]);
+        let series = val.to_polars_series("a").unwrap();
+        assert_eq!(Value::from_polars_series(&series).unwrap(), val);
+        let boxes: Value = Array::from_iter(["ab", "cd"].map(|s| crate::Boxed(s.into()))).into();
+        let series = boxes.to_polars_series("b").unwrap();
+        assert_eq!(Value::from_polars_series(&series).unwrap(), boxes);
+        // Nulls become NaN in numbers but are rejected in strings
+        let ints = Series::new("c".into(), [Some(1i32), None]);
+        let val = Value::from_polars_series(&ints).unwrap();
+        assert!(matches!(&val, Value::Num(arr) if arr.data[1].is_nan()));
+        let strings = Series::new("d".into(), [Some("a"), None]);
+        assert!(Value::from_polars_series(&strings).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "wasm_export")]
+    fn wasm_export() {
+        use crate::wasm_export::*;
+        let runtime = b"\0asm\x01\0\0\0";
+        let mut comp = Compiler::new();
+        comp.load_str("F ← +1\nX ← 5").unwrap();
+        let asm = comp.finish();
+        let module = to_wasm_module_with_runtime(asm.clone(), &["F"], runtime).unwrap();
+        assert!(module.starts_with(runtime));
+        // Custom section id, then size and name
+        let section = &module[runtime.len()..];
+        assert_eq!(section[0], 0);
+        let name_start = 1 + section[1..].iter().position(|&b| b < 0x80).unwrap() + 1;
+        assert_eq!(section[name_start], ASSEMBLY_SECTION.len() as u8);
+        assert!(module.ends_with(b"F"));
+        assert!(to_wasm_module_with_runtime(asm.clone(), &["X"], runtime).is_err());
+        assert!(to_wasm_module_with_runtime(asm, &["F"], b"not wasm").is_err());
+    }
+
+    #[test]
+    fn pad_to_shape() {
+        let env = Uiua::with_safe_sys();
+        let val = Value::from(Array::<f64>::new(2, [1.0, 2.0]));
+        let padded = (val
+            .clone()
+            .pad_to_shape(&Shape::from([2, 3]), &Value::from(0.0), &env))
+        .unwrap();
+        let expected = Array::<f64>::new([2, 3], [1.0, 2.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(padded, Value::from(expected));
+        assert!(val
+            .clone()
+            .pad_to_shape(&Shape::from([1]), &Value::from(0.0), &env)
+            .is_err());
+        // Huge targets error instead of allocating
+        assert!(val
+            .pad_to_shape(&Shape::from([1 << 40, 1 << 40]), &Value::from(0.0), &env)
+            .is_err());
+    }
+
+    #[test]
+    fn assert_shape() {
+        let val = Value::from([[1, 2, 3], [4, 5, 6]]);
+        assert!(val.assert_shape(&[2, 3]).is_ok());
+        assert!(val.assert_shape(&[3, 2]).is_err());
+        assert!(val.assert_rank(2).is_ok());
+        assert!(val.assert_rank(1).is_err());
+    }
+
+    #[test]
+    fn get_doc() {
+        let mut comp = Compiler::new();
+        comp.load_str("# Add\nF ← +\n┌─╴M\n  # Sub\n  G ← -\n└─╴")
+            .unwrap();
+        assert_eq!(comp.get_doc("F"), Some("Add"));
+        assert_eq!(comp.get_doc("G"), None);
+        assert_eq!(comp.get_module_doc("M", "G"), Some("Sub"));
+    }
+
+    #[test]
+    fn exec_streaming() {
+        let asm = Compiler::new().load_str("1 2 3").unwrap().finish();
+        let vals: Vec<Value> = Uiua::exec_streaming(asm, SafeSys::default())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(vals, [Value::from(1), Value::from(2), Value::from(3)]);
+        let asm = Compiler::new().load_str("1 ⍤\"Nope\" 0").unwrap().finish();
+        let mut stream = Uiua::exec_streaming(asm, SafeSys::default());
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+
     #[test]
     fn external_bind_before() {
         let mut comp = Compiler::new();
@@ -0,0 +1,85 @@
+//! Conversion between Uiua values and Polars series
+
+use ::polars::prelude::{DataType, NamedFrom, Series};
+use ecow::EcoVec;
+
+use crate::{Array, Boxed, Span, UiuaError, UiuaResult, Value};
+
+fn polars_error(message: impl Into<String>) -> UiuaError {
+    UiuaError::new(message.into(), Span::Builtin)
+}
+
+impl Value {
+    /// Convert the value to a Polars series
+    ///
+    /// - Rank 1 number arrays become `Float64` series
+    /// - Character arrays become `String` series with one string per rank 1 row
+    /// - Rank 1 arrays of boxed strings become `String` series
+    pub fn to_polars_series(&self, name: &str) -> UiuaResult<Series> {
+        match self {
+            Value::Char(arr) if arr.rank() <= 2 => {
+                let strings: Vec<String> = if arr.rank() <= 1 {
+                    vec![arr.data.iter().collect()]
+                } else {
+                    arr.row_slices().map(|row| row.iter().collect()).collect()
+                };
+                Ok(Series::new(name.into(), strings))
+            }
+            Value::Box(arr) if arr.rank() == 1 => {
+                let strings = (arr.data.iter())
+                    .map(|Boxed(val)| match val {
+                        Value::Char(s) if s.rank() == 1 => Ok(s.data.iter().collect()),
+                        val => Err(polars_error(format!(
+                            "Only boxed strings can be converted to a Polars series, \
+                            but the array contains a {}",
+                            val.type_name()
+                        ))),
+                    })
+                    .collect::<UiuaResult<Vec<String>>>()?;
+                Ok(Series::new(name.into(), strings))
+            }
+            Value::Num(arr) if arr.rank() == 1 => Ok(Series::new(name.into(), arr.data.to_vec())),
+            Value::Byte(arr) if arr.rank() == 1 => {
+                let nums: Vec<f64> = arr.data.iter().map(|&b| b as f64).collect();
+                Ok(Series::new(name.into(), nums))
+            }
+            _ => Err(polars_error(format!(
+                "Cannot convert rank {} {} array to a Polars series. \
+                Only lists of numbers and strings are supported.",
+                self.rank(),
+                self.type_name()
+            ))),
+        }
+    }
+    /// Convert a Polars series to a value
+    ///
+    /// Numeric and boolean series become number arrays.
+    /// Null elements become `NaN`.
+    /// `String` series become lists of boxed strings. They may not contain nulls.
+    pub fn from_polars_series(series: &Series) -> UiuaResult<Self> {
+        let error = |e: ::polars::error::PolarsError| polars_error(e.to_string());
+        match series.dtype() {
+            DataType::String => {
+                let data = (series.str().map_err(error)?.into_iter().enumerate())
+                    .map(|(i, s)| {
+                        let s = s.ok_or_else(|| {
+                            polars_error(format!("Polars string series has a null at index {i}"))
+                        })?;
+                        Ok(Boxed(s.into()))
+                    })
+                    .collect::<UiuaResult<EcoVec<Boxed>>>()?;
+                Ok(Array::new(data.len(), data).into())
+            }
+            dtype if dtype.is_primitive_numeric() || *dtype == DataType::Boolean => {
+                let nums = series.cast(&DataType::Float64).map_err(error)?;
+                let data: EcoVec<f64> = (nums.f64().map_err(error)?.into_iter())
+                    .map(|n| n.unwrap_or(f64::NAN))
+                    .collect();
+                Ok(Array::new(data.len(), data).into())
+            }
+            dtype => Err(polars_error(format!(
+                "Cannot convert Polars {dtype} series to a Uiua value"
+            ))),
+        }
+    }
+}
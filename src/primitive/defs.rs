@@ -3404,6 +3404,13 @@ impl_primitive!(
     (2[1], SplitByKeepEmpty),
     (2, AbsComplex),
     (2, MatrixDiv),
+    (2, MatMul),
+    (1, ArgMax),
+    (1, ArgMin),
+    (3, Diff),
+    (2, Correlate),
+    (1(2), EncodeRle),
+    (2, DecodeRle),
     // Implementation details
     (1, Utf16),
     ([2], RepeatWithInverse),
@@ -3415,4 +3422,6 @@ impl_primitive!(
     (1, ValidateNonBoxedVariant),
     (2(1), ValidateVariant),
     (2(1), TagVariant),
+    /// Check that two values match and both have the shape, variant tag, and label of a data definition's instances
+    (6, MatchDef),
 );
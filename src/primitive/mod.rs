@@ -292,6 +292,12 @@ impl fmt::Display for ImplPrimitive {
             RandomRow => write!(f, "{First}{Un}{Sort}"),
             SortDown => write!(f, "{Select}{Fall}{Dup}"),
             AllSame => write!(f, "all same"),
+            ArgMax => write!(f, "argmax"),
+            ArgMin => write!(f, "argmin"),
+            Diff => write!(f, "diff"),
+            Correlate => write!(f, "correlate"),
+            EncodeRle => write!(f, "encode rle"),
+            DecodeRle => write!(f, "decode rle"),
             Primes => write!(f, "{Un}{Reduce}{Mul}"),
             ReplaceRand => write!(f, "{Gap}{Rand}"),
             ReplaceRand2 => write!(f, "{Gap}{Gap}{Rand}"),
@@ -313,6 +319,7 @@ impl fmt::Display for ImplPrimitive {
             SplitByKeepEmpty => write!(f, "{Un}{Reduce}$\"_…_\""),
             AbsComplex => write!(f, "{Abs}{Complex}"),
             MatrixDiv => write!(f, "{Anti}{Under}{Transpose}({Reduce}{Add}{Mul})"),
+            MatMul => write!(f, "{Table}({Reduce}{Add}{Mul}){Dip}{Transpose}"),
             &ReduceDepth(n) => {
                 for _ in 0..n {
                     write!(f, "{Rows}")?;
@@ -353,6 +360,7 @@ impl fmt::Display for ImplPrimitive {
             ValidateNonBoxedVariant => write!(f, "|…[…]"),
             ValidateVariant => write!(f, "|…°[…]"),
             TagVariant => write!(f, "<tag variant>"),
+            MatchDef => write!(f, "{Match}"),
         }
     }
 }
@@ -934,7 +942,7 @@ impl Primitive {
             }
             Primitive::Type => {
                 let val = env.pop(1)?;
-                env.push(val.type_id());
+                env.push(val.element_type() as u8);
             }
             Primitive::Wait => {
                 let id = env.pop(1)?;
@@ -1162,10 +1170,10 @@ impl Primitive {
                 for i in 0..f.sig.args {
                     args.push(env.pop(i + 1)?);
                 }
+                let limit = env.rt.memo_limit;
                 let mut memo = env.rt.memo.get_or_default().borrow_mut();
                 if let Some(f_memo) = memo.get_mut(&f.node) {
-                    if let Some(outputs) = f_memo.get(&args) {
-                        let outputs = outputs.clone();
+                    if let Some(outputs) = f_memo.get(&args, limit) {
                         drop(memo);
                         for val in outputs {
                             env.push(val);
@@ -1180,10 +1188,8 @@ impl Primitive {
                 env.exec(f.node.clone())?;
                 let outputs = env.clone_stack_top(f.sig.outputs)?;
                 let mut memo = env.rt.memo.get_or_default().borrow_mut();
-                memo.borrow_mut()
-                    .entry(f.node)
-                    .or_default()
-                    .insert(args, outputs.clone());
+                let f_memo = memo.borrow_mut().entry(f.node).or_default();
+                f_memo.insert(args, outputs, limit);
             }
             Primitive::Spawn => {
                 let [f] = get_ops(ops, env)?;
@@ -1215,6 +1221,15 @@ impl Primitive {
 }
 
 impl ImplPrimitive {
+    /// The system function whose backend behavior this primitive uses, if any
+    pub fn sys_op(&self) -> Option<SysOp> {
+        Some(match self {
+            ImplPrimitive::UnRawMode => SysOp::RawMode,
+            ImplPrimitive::UnClip => SysOp::Clip,
+            ImplPrimitive::TryClose => SysOp::Close,
+            _ => return None,
+        })
+    }
     pub(crate) fn run(&self, env: &mut Uiua) -> UiuaResult {
         match self {
             ImplPrimitive::DeshapeSub(i) => {
@@ -1374,6 +1389,15 @@ impl ImplPrimitive {
                     .map_err(|e| env.error(e))?;
             }
             ImplPrimitive::MatrixDiv => env.dyadic_rr_env(Value::matrix_div)?,
+            ImplPrimitive::MatMul => env.dyadic_oo_env(Value::matmul)?,
+            ImplPrimitive::Correlate => env.dyadic_rr_env(Value::correlate)?,
+            ImplPrimitive::EncodeRle => {
+                let val = env.pop(1)?;
+                let (values, lengths) = Value::encode_rle(val, env)?;
+                env.push(lengths);
+                env.push(values);
+            }
+            ImplPrimitive::DecodeRle => env.dyadic_oo_env(Value::decode_rle)?,
             // Unders
             ImplPrimitive::UndoUnBits => {
                 let orig_shape = env.pop(1)?;
@@ -1573,6 +1597,14 @@ impl ImplPrimitive {
             ImplPrimitive::LastWhere => env.monadic_ref_env(Value::last_where)?,
             ImplPrimitive::SortDown => env.monadic_mut(Value::sort_down)?,
             ImplPrimitive::AllSame => env.monadic_ref(Value::all_same)?,
+            ImplPrimitive::ArgMax => env.monadic_ref_env(Value::argmax)?,
+            ImplPrimitive::ArgMin => env.monadic_ref_env(Value::argmin)?,
+            ImplPrimitive::Diff => {
+                let axis = env.pop(1)?.as_nat(env, "Axis must be a natural number")?;
+                let n = env.pop(2)?.as_nat(env, "Order must be a natural number")?;
+                let val = env.pop(3)?;
+                env.push(val.diff(axis, n, env)?);
+            }
             ImplPrimitive::ReplaceRand => {
                 env.pop(1)?;
                 env.push(random());
@@ -1613,7 +1645,7 @@ impl ImplPrimitive {
                         expected.grid_string(false),
                         got.grid_string(false)
                     ),
-                    (true, false) if expected.type_id() != got.type_id() => {
+                    (true, false) if expected.element_type() != got.element_type() => {
                         format!(
                             "expected {} but got {}",
                             expected.grid_string(false),
@@ -1631,7 +1663,7 @@ impl ImplPrimitive {
                         got.type_name(),
                         got.shape()
                     ),
-                    (false, true) if expected.type_id() != got.type_id() => {
+                    (false, true) if expected.element_type() != got.element_type() => {
                         format!(
                             "expected {} but got {}",
                             expected.type_name_plural(),
@@ -1649,7 +1681,7 @@ impl ImplPrimitive {
                         expected.shape(),
                         got.grid_string(false)
                     ),
-                    (false, false) if expected.type_id() != got.type_id() => {
+                    (false, false) if expected.element_type() != got.element_type() => {
                         format!(
                             "expected {} but got {}",
                             expected.type_name_plural(),
@@ -1662,7 +1694,7 @@ impl ImplPrimitive {
                         got.shape()
                     ),
                     (false, false) => {
-                        let different = if expected.type_id() == got.type_id()
+                        let different = if expected.element_type() == got.element_type()
                             && expected.shape() == got.shape()
                         {
                             " different"
@@ -1717,20 +1749,16 @@ impl ImplPrimitive {
                 let type_num = env
                     .pop(1)?
                     .as_nat(env, "Type number must be a natural number")?;
+                let expected = ElementType::from_type_num(type_num)
+                    .ok_or_else(|| env.error(format!("Invalid type number {type_num}")))?;
                 let val = env.pop(2)?;
-                if val.type_id() as usize != type_num {
+                if val.element_type() != expected {
                     let found = if val.element_count() == 1 {
                         val.type_name()
                     } else {
                         val.type_name_plural()
                     };
-                    let expected = match type_num {
-                        0 => "numbers",
-                        1 => "characters",
-                        2 => "boxes",
-                        3 => "complex numbers",
-                        _ => return Err(env.error(format!("Invalid type number {type_num}"))),
-                    };
+                    let expected = expected.plural_name();
                     return Err(env.error(format!("Expected {expected} but found {found}")));
                 }
                 if let ImplPrimitive::ValidateType = self {
@@ -1787,6 +1815,34 @@ impl ImplPrimitive {
                 }
                 env.push(tail);
             }
+            ImplPrimitive::MatchDef => {
+                let label = env
+                    .pop(1)?
+                    .as_string(env, "Definition label must be a string")?;
+                let tag = env.pop(2)?;
+                let boxed = env
+                    .pop(3)?
+                    .as_bool(env, "Definition boxing must be a boolean")?;
+                let len = env
+                    .pop(4)?
+                    .as_nat(env, "Definition length must be a natural number")?;
+                let a = env.pop(5)?;
+                let b = env.pop(6)?;
+                let has_label = |val: &Value| val.meta().label.as_deref() == Some(label.as_str());
+                let is_def = |val: &Value| {
+                    val.rank() > 0
+                        && val.row_count() == len
+                        && matches!(val, Value::Box(_)) == boxed
+                        && if tag.row_count() == 0 {
+                            has_label(val)
+                        } else {
+                            // Variants are labeled on their tag
+                            let head = val.row(0).unboxed();
+                            head == tag.row(0) && has_label(&head)
+                        }
+                };
+                env.push(is_def(&a) && is_def(&b) && a == b);
+            }
             ImplPrimitive::TagVariant => {
                 let mut tag = env.pop(1)?;
                 let val = env.pop(2)?;
@@ -3,8 +3,7 @@
 use std::{
     cell::RefCell,
     cmp::Ordering,
-    collections::HashMap,
-    hash::Hash,
+    collections::{HashMap, VecDeque},
     mem::{size_of, take},
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
@@ -13,9 +12,12 @@ use std::{
     time::Duration,
 };
 
-use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use crossbeam_channel::Sender;
+#[cfg(not(target_arch = "wasm32"))]
+use crossbeam_channel::{Receiver, TryRecvError};
 use ecow::EcoVec;
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use thread_local::ThreadLocal;
 use threadpool::ThreadPool;
 
@@ -25,8 +27,9 @@ use crate::{
     fill::{Fill, FillValue},
     invert::match_format_pattern,
     lex::Span,
-    Array, ArrayLen, Assembly, BindingKind, BindingMeta, Boxed, CodeSpan, Compiler, Function,
-    FunctionId, Ident, Inputs, IntoSysBackend, LocalName, Node, Primitive, Report, SafeSys,
+    Array, ArrayLen, Assembly, BindingKind, BindingMeta, Boxed, ChrootedNativeSys, CodeSpan,
+    Compiler, DiagnosticKind, DynamicFunction, Function, FunctionId, Ident, Inputs, IntoSysBackend,
+    LocalName, Node, Primitive, Report, ReportFragment, ReportKind, SafeSys, SendSyncNative,
     SigNode, Signature, SysBackend, TraceFrame, UiuaError, UiuaErrorKind, UiuaResult, Value,
     VERSION,
 };
@@ -39,6 +42,21 @@ pub struct Uiua {
     pub asm: Assembly,
 }
 
+/// A token bucket for limiting I/O
+struct IoRateLimit {
+    ops_per_second: f64,
+    tokens: f64,
+    last_refill: f64,
+}
+
+impl IoRateLimit {
+    fn refill(&mut self, now: f64) {
+        let capacity = self.ops_per_second.max(1.0);
+        self.tokens = (self.tokens + (now - self.last_refill) * self.ops_per_second).min(capacity);
+        self.last_refill = now;
+    }
+}
+
 /// Runtime-only data
 #[derive(Clone)]
 pub(crate) struct Runtime {
@@ -62,17 +80,21 @@ pub(crate) struct Runtime {
     pub(crate) array_depth: usize,
     /// A limit on the execution duration in milliseconds
     pub(crate) execution_limit: Option<f64>,
+    /// A limit on how often file and network system functions can run
+    io_rate_limit: Option<Arc<Mutex<IoRateLimit>>>,
     /// The time at which execution started
     pub(crate) execution_start: f64,
     /// The recursion limit
     recursion_limit: usize,
+    /// Recursion limits for specific functions
+    call_depth_limits: HashMap<FunctionId, usize>,
     /// Whether the program was interrupted
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) interrupted: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
     #[cfg(target_arch = "wasm32")]
     pub(crate) interrupted: Option<Arc<dyn Fn() -> bool>>,
-    /// Whether to print the time taken to execute each instruction
-    time_instrs: bool,
+    /// How much internal output to emit
+    log_level: LogLevel,
     /// The time at which the last instruction was executed
     last_time: f64,
     /// Arguments passed from the command line
@@ -93,13 +115,124 @@ pub(crate) struct Runtime {
     pub(crate) output_comments: HashMap<usize, Vec<Vec<Value>>>,
     /// Memoized values
     pub(crate) memo: Arc<ThreadLocal<RefCell<MemoMap>>>,
+    /// The maximum number of memoized values per function
+    pub(crate) memo_limit: Option<usize>,
+    /// The maximum number of values that may be left on the stack after a run
+    output_limit: Option<usize>,
+    /// The directory in which compiled assemblies are cached
+    cache_dir: Option<PathBuf>,
     /// The results of tests
     pub(crate) test_results: Vec<UiuaResult>,
     /// Reports to print
     pub(crate) reports: Vec<Report>,
+    /// A hook for resolving imports without the system backend
+    pub(crate) import_hook: Option<ImportHook>,
+    /// A hook called when values are pushed or popped
+    stack_hook: Option<StackHook>,
+    /// A hook that can modify or reject pushed values
+    value_inspector: Option<ValueInspector>,
+    /// An error returned by the value inspector
+    inspector_error: Option<UiuaError>,
+    /// How many times each primitive was run, if profiling is enabled
+    primitive_counts: Option<HashMap<Primitive, u64>>,
+    /// A hook called when execution fails
+    on_error: Option<ErrorHook>,
+    /// Whether the on-error hook has been called for the error currently being returned
+    on_error_called: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|p| p.get())
+        .unwrap_or(1)
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+    asm: String,
+    stack: Vec<Value>,
+    under_stack: Vec<Value>,
+    local_stack: Vec<(usize, Value)>,
+    fill_stack: Vec<(Value, Option<SubSide>)>,
+    unfill_stack: Vec<(Value, Option<SubSide>)>,
 }
 
-type MemoMap = HashMap<Node, HashMap<Vec<Value>, Vec<Value>>>;
+type MemoMap = HashMap<Node, FunctionMemo>;
+
+/// The memoized values of a single function
+///
+/// Uses are only tracked when there is a limit.
+/// Eviction finds the least recently used entry
+/// by skipping stale records in the use queue.
+#[derive(Default)]
+pub(crate) struct FunctionMemo {
+    entries: HashMap<Vec<Value>, (Vec<Value>, u64)>,
+    uses: VecDeque<(u64, Vec<Value>)>,
+    generation: u64,
+}
+
+impl FunctionMemo {
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Get the outputs for some arguments
+    pub(crate) fn get(&mut self, args: &[Value], limit: Option<usize>) -> Option<Vec<Value>> {
+        if limit.is_none() {
+            return self.entries.get(args).map(|(outputs, _)| outputs.clone());
+        }
+        let generation = self.generation;
+        let (outputs, used) = self.entries.get_mut(args)?;
+        *used = generation;
+        let outputs = outputs.clone();
+        self.record_use(args.to_vec());
+        Some(outputs)
+    }
+    /// Insert the outputs for some arguments, evicting the least recently used entry if necessary
+    pub(crate) fn insert(&mut self, args: Vec<Value>, outputs: Vec<Value>, limit: Option<usize>) {
+        let Some(limit) = limit else {
+            self.entries.insert(args, (outputs, 0));
+            return;
+        };
+        if limit == 0 {
+            return;
+        }
+        while self.entries.len() >= limit {
+            let Some((used, key)) = self.uses.pop_front() else {
+                break;
+            };
+            if self.entries.get(&key).is_some_and(|(_, u)| *u == used) {
+                self.entries.remove(&key);
+            }
+        }
+        self.entries
+            .insert(args.clone(), (outputs, self.generation));
+        self.record_use(args);
+    }
+    fn record_use(&mut self, args: Vec<Value>) {
+        self.uses.push_back((self.generation, args));
+        self.generation += 1;
+        // Drop stale records so the queue stays proportional to the number of entries
+        if self.uses.len() > 2 * self.entries.len() + 16 {
+            let entries = &self.entries;
+            (self.uses).retain(|(used, key)| entries.get(key).is_some_and(|(_, u)| u == used));
+        }
+    }
+}
+pub(crate) type ImportHook = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+type StackHook = Arc<dyn Fn(StackEvent) + Send + Sync>;
+type ValueInspector = Arc<dyn Fn(&mut Value) -> UiuaResult + Send + Sync>;
+type ErrorHook = Arc<dyn Fn(&UiuaError, &Uiua) + Send + Sync>;
+
+/// A change to the stack, passed to the hook set with [`Uiua::with_stack_hook`]
+#[derive(Debug, Clone)]
+pub enum StackEvent {
+    /// A value was pushed
+    Push(Value),
+    /// A value was popped
+    Pop(Value),
+}
 
 impl AsRef<Assembly> for Uiua {
     fn as_ref(&self) -> &Assembly {
@@ -113,6 +246,57 @@ impl AsMut<Assembly> for Uiua {
     }
 }
 
+/// An owned snapshot of a function call
+///
+/// See [`Uiua::call_stack_snapshot`]
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    /// The id of the called function, if it has one
+    pub id: Option<FunctionId>,
+    /// The signature of the called function
+    pub sig: Signature,
+    /// The span at which the function was called
+    pub span: Span,
+}
+
+/// How much internal output the interpreter emits
+///
+/// Output is printed with [`SysBackend::print_str_stdout`]
+/// so that it can be captured by the backend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Emit nothing
+    Silent,
+    /// Only emit error reports
+    Error,
+    /// Emit error and warning reports
+    Warn,
+    /// Emit all reports
+    #[default]
+    Info,
+    /// Also emit the time taken to execute each instruction
+    Debug,
+    /// Also emit the stack before each instruction
+    Trace,
+}
+
+/// The lowest log level at which a report is printed
+fn report_level(report: &Report) -> LogLevel {
+    let kinds = report.fragments.iter().filter_map(|frag| match frag {
+        ReportFragment::Colored(_, kind) => Some(*kind),
+        _ => None,
+    });
+    let mut level = LogLevel::Info;
+    for kind in kinds {
+        match kind {
+            ReportKind::Error => return LogLevel::Error,
+            ReportKind::Diagnostic(DiagnosticKind::Warning) => level = LogLevel::Warn,
+            ReportKind::Diagnostic(_) => {}
+        }
+    }
+    level
+}
+
 #[derive(Debug, Clone, Default)]
 pub(crate) struct StackFrame {
     pub(crate) sig: Signature,
@@ -128,8 +312,39 @@ pub(crate) struct StackFrame {
 
 #[derive(Debug, Clone)]
 struct Channel {
+    #[cfg(not(target_arch = "wasm32"))]
     pub send: Sender<Value>,
+    #[cfg(not(target_arch = "wasm32"))]
     pub recv: Receiver<Value>,
+    #[cfg(target_arch = "wasm32")]
+    pub send: Arc<Mutex<VecDeque<Value>>>,
+    #[cfg(target_arch = "wasm32")]
+    pub recv: Arc<Mutex<VecDeque<Value>>>,
+}
+
+impl Channel {
+    /// Create a pair of channels that are connected to each other
+    fn pair() -> (Self, Self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        let ((a_send, b_recv), (b_send, a_recv)) = (
+            crossbeam_channel::unbounded(),
+            crossbeam_channel::unbounded(),
+        );
+        #[cfg(target_arch = "wasm32")]
+        let ((a_send, b_recv), (b_send, a_recv)) = {
+            let (a, b) = (Arc::<Mutex<VecDeque<Value>>>::default(), Arc::default());
+            ((a.clone(), a), (b.clone(), b))
+        };
+        let a = Channel {
+            send: a_send,
+            recv: a_recv,
+        };
+        let b = Channel {
+            send: b_send,
+            recv: b_recv,
+        };
+        (a, b)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -154,16 +369,88 @@ struct Thread {
     #[cfg(not(target_arch = "wasm32"))]
     pub recv: Receiver<UiuaResult<Vec<Value>>>,
     #[cfg(target_arch = "wasm32")]
-    pub result: UiuaResult<Vec<Value>>,
+    pub state: WasmThread,
     pub channel: Channel,
 }
 
+/// A thread on a target without OS threads
+///
+/// Threads are run to completion the first time their parent waits on them
+/// or tries to receive a value from them.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone)]
+enum WasmThread {
+    Pending(Box<Uiua>, SigNode),
+    Done(UiuaResult<Vec<Value>>),
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WasmThread {
+    fn run(&mut self) {
+        if let WasmThread::Pending(env, f) = self {
+            let res = env.exec(f.clone()).map(|_| env.take_stack());
+            *self = WasmThread::Done(res);
+        }
+    }
+    fn finish(mut self) -> UiuaResult<Vec<Value>> {
+        self.run();
+        match self {
+            WasmThread::Done(res) => res,
+            WasmThread::Pending(..) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl std::fmt::Debug for WasmThread {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WasmThread::Pending(..) => write!(f, "<pending thread>"),
+            WasmThread::Done(res) => f.debug_tuple("Done").field(res).finish(),
+        }
+    }
+}
+
 impl Default for Uiua {
     fn default() -> Self {
         Self::with_safe_sys()
     }
 }
 
+/// A node to execute along with the context to execute it in
+///
+/// Used with [`Uiua::exec_in_scope`]
+#[derive(Debug, Clone, Default)]
+pub struct ExecScope {
+    node: Node,
+    fills: Vec<FillValue>,
+    unfills: Vec<FillValue>,
+}
+
+impl ExecScope {
+    /// Create a new scope that executes a node
+    pub fn new(node: impl Into<Node>) -> Self {
+        ExecScope {
+            node: node.into(),
+            ..Default::default()
+        }
+    }
+    /// Set a fill value for the scope
+    ///
+    /// Later fills take precedence over earlier ones
+    pub fn fill(mut self, val: impl Into<Value>) -> Self {
+        self.fills.push(FillValue::new(val, None));
+        self
+    }
+    /// Set an unfill value for the scope
+    ///
+    /// Later unfills take precedence over earlier ones
+    pub fn unfill(mut self, val: impl Into<Value>) -> Self {
+        self.unfills.push(FillValue::new(val, None));
+        self
+    }
+}
+
 /// A mode that affects how non-binding lines are run
 ///
 /// Regardless of the mode, lines with a call to `import` will always be run
@@ -176,6 +463,10 @@ pub enum RunMode {
     Test,
     /// Run everything
     All,
+    /// Compile everything but run nothing
+    ///
+    /// This is respected by every way of running an assembly
+    Dry,
 }
 
 impl FromStr for RunMode {
@@ -185,6 +476,7 @@ impl FromStr for RunMode {
             "normal" => Ok(RunMode::Normal),
             "test" => Ok(RunMode::Test),
             "all" => Ok(RunMode::All),
+            "dry" => Ok(RunMode::Dry),
             _ => Err(format!("unknown run mode `{}`", s)),
         }
     }
@@ -206,11 +498,12 @@ impl Default for Runtime {
             unfill_stack: Vec::new(),
             array_depth: 0,
             backend: Arc::new(SafeSys::default()),
-            time_instrs: false,
+            log_level: LogLevel::default(),
             last_time: 0.0,
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
+            io_rate_limit: None,
             execution_start: 0.0,
             #[cfg(debug_assertions)]
             recursion_limit: 20,
@@ -219,14 +512,25 @@ impl Default for Runtime {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(100),
+            call_depth_limits: HashMap::new(),
             interrupted: None,
             thread_pool: Arc::new(Mutex::new(None)),
             thread: ThisThread::default(),
             output_comments: HashMap::new(),
             memo: Arc::new(ThreadLocal::new()),
+            memo_limit: None,
+            output_limit: None,
+            cache_dir: None,
             unevaluated_constants: HashMap::new(),
             test_results: Vec::new(),
             reports: Vec::new(),
+            import_hook: None,
+            stack_hook: None,
+            value_inspector: None,
+            inspector_error: None,
+            primitive_counts: None,
+            on_error: None,
+            on_error_called: false,
         }
     }
 }
@@ -275,21 +579,98 @@ impl Uiua {
     pub fn take_reports(&mut self) -> Vec<Report> {
         take(&mut self.rt.reports)
     }
+    /// Take all output comment values
+    ///
+    /// The map is keyed by the index of the output comment.
+    /// Each entry has one stack of values for each time the comment was reached.
+    pub fn take_output_comments(&mut self) -> HashMap<usize, Vec<Vec<Value>>> {
+        take(&mut self.rt.output_comments)
+    }
+    /// Get the number of output comments that have values
+    pub fn output_comment_count(&self) -> usize {
+        self.rt.output_comments.len()
+    }
     /// Print all pending reports
+    ///
+    /// Reports containing errors are printed at [`LogLevel::Error`] and above,
+    /// reports containing warnings at [`LogLevel::Warn`] and above,
+    /// and all other reports at [`LogLevel::Info`] and above.
     pub fn print_reports(&mut self) {
         for report in self.take_reports() {
-            println!("{report}"); // Allow println
+            if report_level(&report) <= self.rt.log_level {
+                self.log(&format!("{report}\n"));
+            }
         }
     }
     /// Take the assembly
     pub fn take_asm(&mut self) -> Assembly {
         take(&mut self.asm)
     }
+    /// Serialize the assembly and the runtime's stacks
+    ///
+    /// Functions created from Rust closures cannot be serialized.
+    /// Calling them after [`Uiua::load_state`] results in an error.
+    pub fn save_state(&self) -> UiuaResult<Vec<u8>> {
+        let fills = |stack: &[FillValue]| {
+            (stack.iter())
+                .map(|fv| (fv.value.clone(), fv.side))
+                .collect()
+        };
+        let state = SavedState {
+            asm: self.asm.to_uasm(),
+            stack: self.rt.stack.clone(),
+            under_stack: self.rt.under_stack.clone(),
+            local_stack: self.rt.local_stack.iter().cloned().collect(),
+            fill_stack: fills(&self.rt.fill_stack),
+            unfill_stack: fills(&self.rt.unfill_stack),
+        };
+        serde_json::to_vec(&state).map_err(|e| self.error(format!("Failed to save state: {e}")))
+    }
+    /// Restore a runtime from bytes created by [`Uiua::save_state`]
+    ///
+    /// The restored runtime uses the [`SafeSys`] backend.
+    pub fn load_state(bytes: &[u8]) -> UiuaResult<Self> {
+        let error = |e: String| UiuaError::new(format!("Failed to load state: {e}"), Span::Builtin);
+        let state: SavedState = serde_json::from_slice(bytes).map_err(|e| error(e.to_string()))?;
+        let fills = |stack: Vec<(Value, Option<SubSide>)>| {
+            (stack.into_iter())
+                .map(|(value, side)| FillValue { value, side })
+                .collect()
+        };
+        let mut env = Uiua::with_safe_sys();
+        env.asm = Assembly::from_uasm(&state.asm).map_err(error)?;
+        env.rt.stack = state.stack;
+        env.rt.under_stack = state.under_stack;
+        env.rt.local_stack = state.local_stack.into_iter().collect();
+        env.rt.fill_stack = fills(state.fill_stack);
+        env.rt.unfill_stack = fills(state.unfill_stack);
+        Ok(env)
+    }
     /// Set whether to emit the time taken to execute each instruction
+    ///
+    /// This is equivalent to raising the log level to [`LogLevel::Debug`]
+    /// or lowering it to [`LogLevel::Info`]
     pub fn time_instrs(mut self, time_instrs: bool) -> Self {
-        self.rt.time_instrs = time_instrs;
+        self.rt.log_level = if time_instrs {
+            self.rt.log_level.max(LogLevel::Debug)
+        } else {
+            self.rt.log_level.min(LogLevel::Info)
+        };
         self
     }
+    /// Set how much internal output to emit
+    pub fn with_log_level(mut self, level: LogLevel) -> Self {
+        self.rt.log_level = level;
+        self
+    }
+    /// Get the log level
+    pub fn log_level(&self) -> LogLevel {
+        self.rt.log_level
+    }
+    /// Print internal output through the backend
+    fn log(&self, s: &str) {
+        _ = self.rt.backend.print_str_stdout(s);
+    }
     /// Limit the execution duration
     pub fn with_execution_limit(mut self, limit: Duration) -> Self {
         self.rt.execution_limit = Some(limit.as_secs_f64());
@@ -300,6 +681,20 @@ impl Uiua {
         self.rt.execution_limit = limit.map(|limit| limit.as_secs_f64());
         self
     }
+    /// Limit how many file, network, and command system functions can run per second
+    ///
+    /// Up to `ops_per_second` of them can run in a burst.
+    /// Once the limit is reached, a system function waits up to 100ms for the limit to allow it
+    /// before failing.
+    pub fn with_io_rate_limit(mut self, ops_per_second: u32) -> Self {
+        let ops_per_second = ops_per_second as f64;
+        self.rt.io_rate_limit = Some(Arc::new(Mutex::new(IoRateLimit {
+            ops_per_second,
+            tokens: ops_per_second,
+            last_refill: self.rt.backend.now(),
+        })));
+        self
+    }
     /// Set the recursion limit
     ///
     /// Default is 100 for release builds and 20 for debug builds
@@ -307,6 +702,67 @@ impl Uiua {
         self.rt.recursion_limit = limit;
         self
     }
+    /// Set recursion limits for specific functions
+    ///
+    /// Calling one of these functions fails if it would have more than
+    /// the given number of active calls on the call stack.
+    /// The overall recursion limit still applies.
+    pub fn with_call_depth_limit(mut self, per_function_id: HashMap<FunctionId, usize>) -> Self {
+        self.rt.call_depth_limits = per_function_id;
+        self
+    }
+    /// Limit the number of memoized values for each function
+    ///
+    /// When the limit is reached, the least recently used value is evicted.
+    pub fn with_memo_limit(mut self, max_entries: usize) -> Self {
+        self.rt.memo_limit = Some(max_entries);
+        self
+    }
+    /// Restrict file access to a root directory
+    ///
+    /// The current system backend is wrapped in a [`ChrootedNativeSys`].
+    /// See its documentation for which operations are confined and which are disabled.
+    pub fn with_chroot(mut self, root: PathBuf) -> Self {
+        self.rt.backend = Arc::new(ChrootedNativeSys::new(self.rt.backend.clone(), root));
+        self
+    }
+    /// Limit the number of values that may be left on the stack after a run
+    ///
+    /// If a run succeeds but leaves more values than this, the stack is truncated
+    /// to the limit and an error is returned.
+    pub fn with_output_limit(mut self, max_stack_values: usize) -> Self {
+        self.rt.output_limit = Some(max_stack_values);
+        self
+    }
+    /// Cache compiled assemblies in a directory
+    ///
+    /// [`Uiua::run_file`] will look for an assembly keyed by the hash of the file's contents
+    /// before compiling, and will save the assembly there after compiling.
+    /// A cached assembly is only used if none of the files it was compiled from have changed.
+    ///
+    /// The cache is read and written through the system backend,
+    /// so nothing is cached if the backend does not support file access.
+    /// On a cache hit, the returned [`Compiler`] has the cached assembly but no code metadata.
+    pub fn with_cache_dir(mut self, dir: PathBuf) -> Self {
+        self.rt.cache_dir = Some(dir);
+        self
+    }
+    /// Set the maximum number of threads used by `pool`
+    ///
+    /// This gives the runtime its own thread pool.
+    /// The default is the number of available CPU cores.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_thread_count(mut self, n: usize) -> Self {
+        self.rt.thread_pool = Arc::new(Mutex::new(Some(ThreadPool::new(n.max(1)))));
+        self
+    }
+    /// Get the maximum number of threads used by `pool`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn thread_count(&self) -> usize {
+        (self.rt.thread_pool.lock().as_ref())
+            .map(ThreadPool::max_count)
+            .unwrap_or_else(default_thread_count)
+    }
     /// Set the interrupted hook
     #[cfg(not(target_arch = "wasm32"))]
     pub fn with_interrupt_hook(mut self, hook: impl Fn() -> bool + Send + Sync + 'static) -> Self {
@@ -319,6 +775,92 @@ impl Uiua {
         self.rt.interrupted = Some(Arc::new(hook));
         self
     }
+    /// Set a hook for resolving imports
+    ///
+    /// The hook receives the import path and may return Uiua source code to use for the module.
+    /// If it returns `None`, the module is loaded through the system backend as usual.
+    ///
+    /// The hook is not called for compilers that have a
+    /// [`Compiler::with_import_resolver`] resolver, which handles every import itself.
+    pub fn with_import_hook(
+        mut self,
+        hook: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.rt.import_hook = Some(Arc::new(hook));
+        self
+    }
+    /// Set a hook that is called whenever a value is pushed or popped
+    ///
+    /// The hook is called from [`Uiua::push`], [`Uiua::push_all`], and [`Uiua::pop`],
+    /// so it should be cheap.
+    pub fn with_stack_hook(mut self, hook: impl Fn(StackEvent) + Send + Sync + 'static) -> Self {
+        self.rt.stack_hook = Some(Arc::new(hook));
+        self
+    }
+    /// Add a hook that is called on every value before it is pushed
+    ///
+    /// The hook may modify the value.
+    /// If it returns an error, the value is not pushed,
+    /// and execution stops with that error once the current instruction finishes.
+    ///
+    /// If a hook was already added, the new one runs after it on the value it produced.
+    pub fn with_value_inspector(
+        mut self,
+        hook: impl Fn(&mut Value) -> UiuaResult + Send + Sync + 'static,
+    ) -> Self {
+        self.rt.value_inspector = Some(match self.rt.value_inspector.take() {
+            Some(prev) => Arc::new(move |val: &mut Value| {
+                prev(val)?;
+                hook(val)
+            }),
+            None => Arc::new(hook),
+        });
+        self
+    }
+    /// Add a function that transforms every value before it is pushed
+    ///
+    /// This is a shorthand for a [`Uiua::with_value_inspector`] hook that never fails.
+    /// It composes with other inspectors and transformers in the order they were added.
+    pub fn with_value_transformer(
+        self,
+        f: impl Fn(Value) -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.with_value_inspector(move |val| {
+            *val = f(take(val));
+            Ok(())
+        })
+    }
+    /// Set a hook that is called when an instruction fails
+    ///
+    /// The hook is called once per error, from the instruction where the error occurred,
+    /// so the stack can be inspected before it is unwound.
+    /// Errors that are later caught by [`Primitive::Try`] still call the hook.
+    pub fn with_on_error(
+        mut self,
+        hook: impl Fn(&UiuaError, &Uiua) + Send + Sync + 'static,
+    ) -> Self {
+        self.rt.on_error = Some(Arc::new(hook));
+        self
+    }
+    /// Enable counting how many times each primitive is run
+    ///
+    /// Get the counts with [`Uiua::primitive_histogram`].
+    /// Primitives run in spawned threads are not counted.
+    pub fn with_primitive_profiling(mut self) -> Self {
+        self.rt.primitive_counts = Some(Default::default());
+        self
+    }
+    /// Get how many times each primitive has been run
+    ///
+    /// This is empty unless [`Uiua::with_primitive_profiling`] was used.
+    pub fn primitive_histogram(&self) -> HashMap<Primitive, u64> {
+        self.rt.primitive_counts.clone().unwrap_or_default()
+    }
+    fn count_primitive(&mut self, prim: Primitive) {
+        if let Some(counts) = &mut self.rt.primitive_counts {
+            *counts.entry(prim).or_default() += 1;
+        }
+    }
     /// Set the command line arguments
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.rt.cli_arguments = args;
@@ -347,6 +889,7 @@ impl Uiua {
         compile: impl FnOnce(&mut Compiler) -> UiuaResult<&mut Compiler>,
     ) -> UiuaResult<Compiler> {
         let mut comp = Compiler::with_backend(self.rt.backend.clone());
+        comp.set_import_hook(self.rt.import_hook.clone());
         let asm = compile(&mut comp)?.finish();
         self.run_asm(asm)?;
         comp.set_backend(SafeSys::default());
@@ -358,7 +901,63 @@ impl Uiua {
     }
     /// Run a file as Uiua code
     pub fn run_file<P: AsRef<Path>>(&mut self, path: P) -> UiuaResult<Compiler> {
-        self.compile_run(|comp| comp.load_file(path))
+        let Some(dir) = self.rt.cache_dir.clone() else {
+            return self.compile_run(|comp| comp.load_file(path));
+        };
+        let path = path.as_ref();
+        let backend = self.rt.backend.clone();
+        let cache_path = backend.file_read_all(path).ok().map(|input| {
+            let hash = stable_hash(&[crate::VERSION.as_bytes(), &input]);
+            dir.join(format!("{hash:016x}.asm"))
+        });
+        let cached = (cache_path.as_ref())
+            .and_then(|cache_path| backend.file_read_all(cache_path).ok())
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|uasm| Assembly::from_uasm(&uasm).ok())
+            // Imported files must not have changed since the assembly was compiled
+            .filter(|asm| {
+                asm.inputs.files.iter().all(|entry| {
+                    (backend.file_read_all(entry.key()))
+                        .is_ok_and(|src| src == entry.value().as_bytes())
+                })
+            });
+        if let Some(asm) = cached {
+            let comp = Compiler::with_backend(SafeSys::default()).with_assembly(asm.clone());
+            self.run_asm(asm)?;
+            return Ok(comp);
+        }
+        self.compile_run(|comp| {
+            comp.load_file(path)?;
+            if let Some(cache_path) = &cache_path {
+                if backend.make_dir(&dir).is_ok() {
+                    let uasm = comp.assembly().to_uasm();
+                    _ = backend.file_write_all(cache_path, uasm.as_bytes());
+                }
+            }
+            Ok(comp)
+        })
+    }
+    /// Run several strings as Uiua code in sequence, sharing a single assembly
+    ///
+    /// Each snippet starts with an empty stack but can see the bindings of all previous snippets.
+    /// The values left on the stack by each snippet are returned.
+    pub fn run_str_batch(&mut self, snippets: &[&str]) -> Vec<UiuaResult<Vec<Value>>> {
+        let mut comp =
+            Compiler::with_backend(self.rt.backend.clone()).with_assembly(self.take_asm());
+        comp.set_import_hook(self.rt.import_hook.clone());
+        let mut results = Vec::with_capacity(snippets.len());
+        for snippet in snippets {
+            self.take_stack();
+            // Previous roots have already been run
+            comp.assembly_mut().root = Node::empty();
+            let res = match comp.load_str(snippet) {
+                Ok(comp) => self.run_compiler(comp),
+                Err(e) => Err(e),
+            };
+            results.push(res.map(|_| self.take_stack()));
+        }
+        self.asm = comp.finish();
+        results
     }
     /// Run from a compiler
     ///
@@ -380,10 +979,16 @@ impl Uiua {
         }
     }
     /// Run a Uiua assembly
+    ///
+    /// Nothing is run if the assembly was compiled with [`RunMode::Dry`]
     pub fn run_asm(&mut self, asm: Assembly) -> UiuaResult {
         fn run_asm(env: &mut Uiua, asm: Assembly) -> UiuaResult {
             env.asm = asm;
+            if env.asm.dry_run {
+                return Ok(());
+            }
             env.rt.execution_start = env.rt.backend.now();
+            env.rt.inspector_error = None;
             let mut res = env
                 .catching_crash(|env| env.exec(env.asm.root.clone()))
                 .unwrap_or_else(Err);
@@ -403,11 +1008,35 @@ impl Uiua {
                 }
                 (env.rt.reports).push(Report::tests(successes, total_run - successes, not_run));
             }
+            if let Some(limit) = env.rt.output_limit {
+                let count = env.rt.stack.len();
+                if res.is_ok() && count > limit {
+                    env.rt.stack.truncate(limit);
+                    res = Err(env.error(format!(
+                        "Program produced {count} values, \
+                        but the output limit is {limit}"
+                    )));
+                }
+            }
             if res.is_err() {
                 env.rt = Runtime {
                     backend: env.rt.backend.clone(),
                     execution_limit: env.rt.execution_limit,
-                    time_instrs: env.rt.time_instrs,
+                    recursion_limit: env.rt.recursion_limit,
+                    call_depth_limits: env.rt.call_depth_limits.clone(),
+                    io_rate_limit: env.rt.io_rate_limit.clone(),
+                    log_level: env.rt.log_level,
+                    import_hook: env.rt.import_hook.clone(),
+                    stack_hook: env.rt.stack_hook.clone(),
+                    value_inspector: env.rt.value_inspector.clone(),
+                    inspector_error: None,
+                    primitive_counts: take(&mut env.rt.primitive_counts),
+                    on_error: env.rt.on_error.clone(),
+                    on_error_called: false,
+                    memo_limit: env.rt.memo_limit,
+                    output_limit: env.rt.output_limit,
+                    cache_dir: env.rt.cache_dir.clone(),
+                    thread_pool: env.rt.thread_pool.clone(),
                     output_comments: take(&mut env.rt.output_comments),
                     reports: take(&mut env.rt.reports),
                     stack: take(&mut env.rt.stack),
@@ -418,6 +1047,44 @@ impl Uiua {
         }
         run_asm(self, asm)
     }
+    /// Run a Uiua assembly and stream the values it leaves on the stack
+    ///
+    /// The values are yielded top first.
+    /// If running fails, the error is yielded instead.
+    ///
+    /// On native targets, the assembly is run on its own thread,
+    /// and each value is only popped once the previous one has been consumed.
+    /// Dropping the iterator early stops the stream.
+    pub fn exec_streaming(
+        asm: Assembly,
+        backend: impl IntoSysBackend,
+    ) -> impl Iterator<Item = UiuaResult<Value>> {
+        let mut env = Uiua::with_backend(backend);
+        let stream = move |send: Sender<UiuaResult<Value>>| {
+            if let Err(e) = env.run_asm(asm) {
+                _ = send.send(Err(e));
+                return;
+            }
+            while let Some(val) = env.rt.stack.pop() {
+                if send.send(Ok(val)).is_err() {
+                    break;
+                }
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let recv = {
+            let (send, recv) = crossbeam_channel::bounded(0);
+            std::thread::spawn(move || stream(send));
+            recv
+        };
+        #[cfg(target_arch = "wasm32")]
+        let recv = {
+            let (send, recv) = crossbeam_channel::unbounded();
+            stream(send);
+            recv
+        };
+        recv.into_iter()
+    }
     fn catching_crash<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> UiuaResult<T> {
         match catch_unwind(AssertUnwindSafe(|| f(self))) {
             Ok(res) => Ok(res),
@@ -468,6 +1135,7 @@ impl Uiua {
     }
     fn exec_impl(&mut self, node: Node) -> UiuaResult {
         let mut formatted_node = String::new();
+        self.rt.on_error_called = false;
 
         // Uncomment to debug
         // for val in self.rt.stack.iter().rev() {
@@ -486,20 +1154,34 @@ impl Uiua {
         // }
         // println!("\n    {node:?}");
 
-        if self.rt.time_instrs {
+        if self.rt.log_level >= LogLevel::Trace {
+            let mut stack = String::new();
+            for val in self.rt.stack.iter().rev() {
+                stack.push_str(&format!("{val:?} "));
+            }
+            if self.rt.stack.is_empty() {
+                stack.push_str("(empty)");
+            }
+            self.log(&format!("  ⋯ {}\n", stack.trim_end()));
+        }
+        if self.rt.log_level >= LogLevel::Debug {
             formatted_node = format!("{node:?}");
             self.rt.last_time = self.rt.backend.now();
         }
         let res = match node {
             Node::Run(nodes) => nodes.into_iter().try_for_each(|node| self.exec(node)),
-            Node::Prim(prim, span) => self.with_prim_span(span, Some(prim), |env| prim.run(env)),
+            Node::Prim(prim, span) => {
+                self.count_primitive(prim);
+                self.with_prim_span(span, Some(prim), |env| prim.run(env))
+            }
             Node::ImplPrim(prim, span) => self.with_span(span, |env| prim.run(env)),
             Node::Mod(prim, args, span) => {
+                self.count_primitive(prim);
                 self.with_prim_span(span, Some(prim), |env| prim.run_mod(args, env))
             }
             Node::ImplMod(prim, args, span) => self.with_span(span, |env| prim.run_mod(args, env)),
             Node::Push(val) => {
-                self.rt.stack.push(val);
+                self.push(val);
                 Ok(())
             }
             Node::CallGlobal(index, _) => {
@@ -511,7 +1193,7 @@ impl Uiua {
                 })?;
                 match binding.kind.clone() {
                     BindingKind::Const(Some(val)) => {
-                        self.rt.stack.push(val);
+                        self.push(val);
                         Ok(())
                     }
                     BindingKind::Const(None) => {
@@ -531,9 +1213,9 @@ impl Uiua {
                             ))
                         }
                     }
-                    BindingKind::Func(f) => {
-                        self.respect_recursion_limit().and_then(|_| self.call(&f))
-                    }
+                    BindingKind::Func(f) => self
+                        .respect_recursion_limit(&f.id)
+                        .and_then(|_| self.call(&f)),
                     BindingKind::Import { .. } | BindingKind::Module(_) | BindingKind::Scope(_) => {
                         Err(self.error(
                             "Called module global. \
@@ -625,30 +1307,19 @@ impl Uiua {
             Node::ValidateType {
                 index,
                 name,
-                type_num,
+                ty,
                 span,
             } => {
                 let name = name.clone();
                 self.with_span(span, |env| {
                     let val = env.pop(index)?;
-                    if val.type_id() != type_num {
+                    if val.element_type() != ty {
                         let found = if val.element_count() == 1 {
                             val.type_name()
                         } else {
                             val.type_name_plural()
                         };
-                        let expected = match type_num {
-                            0 => "numbers",
-                            1 => "complex numbers",
-                            2 => "characters",
-                            3 => "boxes",
-                            _ => {
-                                return Err(env.error(format!(
-                                    "Invalid type number {type_num}. \
-                                        This is a bug in the interpreter."
-                                )));
-                            }
-                        };
+                        let expected = ty.plural_name();
                         return Err(env.error(format!(
                             "Field `{name}` should be {expected} but found {found}"
                         )));
@@ -745,10 +1416,17 @@ impl Uiua {
                 Ok(())
             }),
             Node::NoInline(inner) => self.exec(inner),
+            Node::CommentedOut(_) => Ok(()),
             Node::TrackCaller(inner) => {
                 self.rt.call_stack.last_mut().unwrap().track_caller = true;
                 self.exec(inner)
             }
+            Node::Repeat { count, inner, .. } => {
+                for _ in 0..count {
+                    self.exec(inner.node.clone())?;
+                }
+                Ok(())
+            }
             Node::WithLocal { def, inner, span } => self.with_span(span, |env| {
                 let val = env.remove_nth_back(inner.sig.args)?;
                 env.rt.local_stack.push((def, val));
@@ -823,21 +1501,74 @@ impl Uiua {
                 Ok(())
             }),
         };
-        if self.rt.time_instrs {
+        if self.rt.log_level >= LogLevel::Debug {
             let end_time = self.rt.backend.now();
             let padding = self.rt.call_stack.len().saturating_sub(1) * 2;
-            #[rustfmt::skip]
-            println!( // Allow println
-                "  ⏲{:padding$}{:.2}ms - {}",
+            self.log(&format!(
+                "  ⏲{:padding$}{:.2}ms - {}\n",
                 "",
                 end_time - self.rt.last_time,
                 formatted_node
-            );
+            ));
             self.rt.last_time = self.rt.backend.now();
         }
-        self.respect_execution_limit()?;
+        let res = if let Some(e) = self.rt.inspector_error.take() {
+            Err(e)
+        } else {
+            self.respect_execution_limit().and(res)
+        };
+        if let Err(e) = &res {
+            if !self.rt.on_error_called {
+                if let Some(hook) = self.rt.on_error.clone() {
+                    self.rt.on_error_called = true;
+                    hook(e, self);
+                }
+            }
+        }
         res
     }
+    /// Get the time elapsed since the current or most recent execution started
+    pub fn execution_elapsed(&self) -> Duration {
+        let elapsed = self.rt.backend.now() - self.rt.execution_start;
+        Duration::from_secs_f64(elapsed.max(0.0))
+    }
+    /// Wait until the I/O rate limit allows another operation
+    pub(crate) fn respect_io_rate_limit(&self) -> UiuaResult {
+        const MAX_WAIT: f64 = 0.1;
+        let Some(limit) = &self.rt.io_rate_limit else {
+            return Ok(());
+        };
+        let wait = {
+            let mut limit = limit.lock();
+            limit.refill(self.rt.backend.now());
+            if limit.tokens >= 1.0 {
+                limit.tokens -= 1.0;
+                return Ok(());
+            }
+            ((1.0 - limit.tokens) / limit.ops_per_second).min(MAX_WAIT)
+        };
+        // The lock is not held while waiting so that other threads are not blocked
+        if self.rt.backend.sleep(wait).is_err() {
+            // Backends that cannot sleep must still be throttled
+            #[cfg(not(target_arch = "wasm32"))]
+            std::thread::sleep(Duration::from_secs_f64(wait));
+            #[cfg(target_arch = "wasm32")]
+            {
+                let end = self.rt.backend.now() + wait;
+                while self.rt.backend.now() < end {}
+            }
+        }
+        let mut limit = limit.lock();
+        limit.refill(self.rt.backend.now());
+        if limit.tokens < 1.0 {
+            return Err(self.error(format!(
+                "I/O rate limit of {} operations per second exceeded",
+                limit.ops_per_second
+            )));
+        }
+        limit.tokens -= 1.0;
+        Ok(())
+    }
     /// Timeout if an execution limit is set and has been exceeded
     pub fn respect_execution_limit(&self) -> UiuaResult {
         if let Some(limit) = self.rt.execution_limit {
@@ -884,6 +1615,81 @@ impl Uiua {
         let call_span = self.span_index();
         self.call_with_span(f, call_span)
     }
+    /// Push arguments, call a function, and return its outputs
+    ///
+    /// Arguments are pushed in order, so the last one ends up on top of the stack.
+    /// Exactly `f.sig.outputs` values are popped and returned in the order they were pushed.
+    ///
+    /// If the call fails, the stack is restored to its state before the arguments were pushed.
+    pub fn call_returning(
+        &mut self,
+        f: &Function,
+        args: impl IntoIterator<Item = Value>,
+    ) -> UiuaResult<Vec<Value>> {
+        let stack = self.rt.stack.clone();
+        let under_height = self.under_stack_height();
+        self.rt.inspector_error = None;
+        for arg in args {
+            self.push(arg);
+        }
+        if let Some(e) = self.rt.inspector_error.take() {
+            self.rt.stack = stack;
+            return Err(e);
+        }
+        let res = self.call(f).and_then(|_| self.pop_n(f.sig.outputs));
+        if res.is_err() {
+            self.rt.stack = stack;
+            self.truncate_under_stack(under_height);
+        }
+        res
+    }
+    /// Execute a node in a scope
+    ///
+    /// The fill context is restored afterward, even if execution fails.
+    pub fn exec_in_scope(&mut self, scope: ExecScope) -> UiuaResult {
+        let fill_height = self.rt.fill_stack.len();
+        let unfill_height = self.rt.unfill_stack.len();
+        self.rt.fill_stack.extend(scope.fills);
+        self.rt.unfill_stack.extend(scope.unfills);
+        let res = self.exec(scope.node);
+        self.rt.fill_stack.truncate(fill_height);
+        self.rt.unfill_stack.truncate(unfill_height);
+        res
+    }
+    /// Execute each of several nodes, continuing after errors
+    ///
+    /// If a node fails, the stack is restored to its state before that node ran.
+    /// The result of each node is returned in order.
+    pub fn exec_all(&mut self, nodes: &[Node]) -> Vec<UiuaResult> {
+        (nodes.iter())
+            .map(|node| {
+                let stack = self.rt.stack.clone();
+                let under_height = self.under_stack_height();
+                let res = self.exec(node.clone());
+                if res.is_err() {
+                    self.rt.stack = stack;
+                    self.truncate_under_stack(under_height);
+                }
+                res
+            })
+            .collect()
+    }
+    /// Create a function from a Rust closure
+    ///
+    /// Uiua functions are not values, so the function cannot be pushed onto the stack.
+    /// Instead, the returned handle can be called with [`Uiua::call`] or [`Uiua::call_returning`],
+    /// or bound with [`Compiler::bind_function`] before compiling code that uses it.
+    pub fn create_function(
+        &mut self,
+        sig: impl Into<Signature>,
+        f: impl Fn(&mut Uiua) -> UiuaResult + SendSyncNative + 'static,
+    ) -> Function {
+        let sig = sig.into();
+        let index = self.asm.dynamic_functions.len();
+        self.asm.dynamic_functions.push(Arc::new(f));
+        let df = DynamicFunction { index, sig };
+        (self.asm).add_function(FunctionId::Unnamed, sig, Node::Dynamic(df))
+    }
     /// Call and truncate the stack to before the args were pushed if the call fails
     pub(crate) fn exec_clean_stack(&mut self, sn: SigNode) -> UiuaResult {
         let sig = sn.sig;
@@ -1017,21 +1823,11 @@ impl Uiua {
     }
     /// Construct an error with the current span
     pub fn error(&self, message: impl ToString) -> UiuaError {
-        UiuaErrorKind::Run {
-            message: self.span().clone().sp(message.to_string()),
-            info: Vec::new(),
-            inputs: self.inputs().clone().into(),
-        }
-        .into()
+        UiuaError::new(message, self.span()).with_inputs(self.inputs().clone())
     }
     /// Construct an error with a custom span
     pub fn error_with_span(&self, span: Span, message: impl ToString) -> UiuaError {
-        UiuaErrorKind::Run {
-            message: span.sp(message.to_string()),
-            info: Vec::new(),
-            inputs: self.inputs().clone().into(),
-        }
-        .into()
+        UiuaError::new(message, span).with_inputs(self.inputs().clone())
     }
     #[allow(dead_code)]
     pub(crate) fn error_maybe_span(
@@ -1047,10 +1843,13 @@ impl Uiua {
     }
     /// Pop a value from the stack
     pub fn pop(&mut self, arg: impl StackArg) -> UiuaResult<Value> {
-        self.rt
-            .stack
-            .pop()
-            .ok_or_else(|| self.error(format!("Stack was empty when getting {}", arg.arg_name())))
+        let val = (self.rt.stack.pop()).ok_or_else(|| {
+            self.error(format!("Stack was empty when getting {}", arg.arg_name()))
+        })?;
+        if let Some(hook) = &self.rt.stack_hook {
+            hook(StackEvent::Pop(val.clone()));
+        }
+        Ok(val)
     }
     /// Pop a value and try to convert it
     pub fn pop_convert<T>(
@@ -1133,14 +1932,30 @@ impl Uiua {
     }
     /// Push a value onto the stack
     pub fn push<V: Into<Value>>(&mut self, val: V) {
-        self.rt.stack.push(val.into());
+        let mut val = val.into();
+        if let Some(inspector) = &self.rt.value_inspector {
+            if let Err(e) = inspector(&mut val) {
+                self.rt.inspector_error.get_or_insert(e);
+                return;
+            }
+        }
+        if let Some(hook) = &self.rt.stack_hook {
+            hook(StackEvent::Push(val.clone()));
+        }
+        self.rt.stack.push(val);
     }
     pub(crate) fn push_under(&mut self, val: Value) {
         self.rt.under_stack.push(val);
     }
     /// Push several values onto the stack
     pub fn push_all<V: Into<Value>>(&mut self, vals: impl IntoIterator<Item = V>) {
-        self.rt.stack.extend(vals.into_iter().map(Into::into));
+        if self.rt.stack_hook.is_some() || self.rt.value_inspector.is_some() {
+            for val in vals {
+                self.push(val);
+            }
+        } else {
+            self.rt.stack.extend(vals.into_iter().map(Into::into));
+        }
     }
     /// Take the entire stack
     pub fn take_stack(&mut self) -> Vec<Value> {
@@ -1262,6 +2077,17 @@ impl Uiua {
     pub fn stack_mut(&mut self) -> &mut [Value] {
         &mut self.rt.stack
     }
+    /// Get a reference to the top `n` values of the stack
+    ///
+    /// The most recently pushed value is last.
+    pub fn peek_stack(&self, n: usize) -> UiuaResult<&[Value]> {
+        let height = self.require_height(n)?;
+        Ok(&self.rt.stack[height..])
+    }
+    /// Get a reference to the top value of the stack
+    pub fn peek_top(&self) -> UiuaResult<&Value> {
+        self.peek_stack(1).map(|vals| &vals[0])
+    }
     /// Get all bound values in the assembly
     ///
     /// Bindings are only given values once the assembly has been run successfully
@@ -1414,6 +2240,18 @@ impl Uiua {
             self.last_unfill()
         }
     }
+    /// Get the fill stack
+    ///
+    /// The most recently set fill is last
+    pub fn fill_stack(&self) -> &[FillValue] {
+        &self.rt.fill_stack
+    }
+    /// Get the unfill stack
+    ///
+    /// The most recently set unfill is last
+    pub fn unfill_stack(&self) -> &[FillValue] {
+        &self.rt.unfill_stack
+    }
     pub(crate) fn last_fill(&self) -> Option<&FillValue> {
         self.rt.fill_stack.last()
     }
@@ -1498,7 +2336,29 @@ impl Uiua {
     pub(crate) fn call_frames(&self) -> impl DoubleEndedIterator<Item = &StackFrame> {
         self.rt.call_stack.iter()
     }
-    pub(crate) fn respect_recursion_limit(&mut self) -> UiuaResult {
+    /// Get an owned copy of the current call stack
+    ///
+    /// The outermost call comes first.
+    pub fn call_stack_snapshot(&self) -> Vec<CallFrame> {
+        self.call_frames()
+            .map(|frame| CallFrame {
+                id: frame.id.clone(),
+                sig: frame.sig,
+                span: self.get_span(frame.call_span),
+            })
+            .collect()
+    }
+    pub(crate) fn respect_recursion_limit(&mut self, id: &FunctionId) -> UiuaResult {
+        if let Some(&limit) = self.rt.call_depth_limits.get(id) {
+            let depth = (self.rt.call_stack.iter())
+                .filter(|frame| frame.id.as_ref() == Some(id))
+                .count();
+            if depth >= limit {
+                return Err(self.error(format!(
+                    "Recursion limit reached for {id}. The limit is {limit}."
+                )));
+            }
+        }
         if self.rt.call_stack.len() > self.rt.recursion_limit {
             Err(
                 self.error(if cfg!(target_arch = "wasm32") || cfg!(debug_assertions) {
@@ -1528,13 +2388,9 @@ impl Uiua {
                 self.rt.stack.len()
             )))?;
         }
-        let (this_send, child_recv) = crossbeam_channel::unbounded();
-        let (child_send, this_recv) = crossbeam_channel::unbounded();
+        let (this_channel, child_channel) = Channel::pair();
         let thread = ThisThread {
-            parent: Some(Channel {
-                send: child_send,
-                recv: child_recv,
-            }),
+            parent: Some(child_channel),
             ..ThisThread::default()
         };
         let make_env = || Uiua {
@@ -1551,20 +2407,32 @@ impl Uiua {
                 recur_stack: self.rt.recur_stack.clone(),
                 call_stack: Vec::from_iter(self.rt.call_stack.last().cloned()),
                 array_depth: 0,
-                time_instrs: self.rt.time_instrs,
+                log_level: self.rt.log_level,
                 last_time: self.rt.last_time,
                 cli_arguments: self.rt.cli_arguments.clone(),
                 cli_file_path: self.rt.cli_file_path.clone(),
                 backend: self.rt.backend.clone(),
                 execution_limit: self.rt.execution_limit,
+                io_rate_limit: self.rt.io_rate_limit.clone(),
                 execution_start: self.rt.execution_start,
                 recursion_limit: self.rt.recursion_limit,
+                call_depth_limits: self.rt.call_depth_limits.clone(),
                 interrupted: self.rt.interrupted.clone(),
                 output_comments: HashMap::new(),
                 memo: self.rt.memo.clone(),
+                memo_limit: self.rt.memo_limit,
+                output_limit: self.rt.output_limit,
+                cache_dir: self.rt.cache_dir.clone(),
                 unevaluated_constants: HashMap::new(),
                 test_results: Vec::new(),
                 reports: Vec::new(),
+                import_hook: self.rt.import_hook.clone(),
+                stack_hook: self.rt.stack_hook.clone(),
+                value_inspector: self.rt.value_inspector.clone(),
+                inspector_error: None,
+                primitive_counts: None,
+                on_error: self.rt.on_error.clone(),
+                on_error_called: false,
                 thread_pool: self.rt.thread_pool.clone(),
                 thread,
             },
@@ -1573,14 +2441,9 @@ impl Uiua {
         let recv = {
             let (send, recv) = crossbeam_channel::unbounded();
             if _pool {
-                let max_threads = std::thread::available_parallelism()
-                    .map(|p| p.get())
-                    .unwrap_or(1);
                 let mut pool = self.rt.thread_pool.lock();
-                if pool.is_none() {
-                    *pool = Some(ThreadPool::new(max_threads));
-                }
-                let pool = pool.as_mut().unwrap();
+                let pool = pool.get_or_insert_with(|| ThreadPool::new(default_thread_count()));
+                let max_threads = pool.max_count();
                 while pool.active_count() >= max_threads {
                     std::thread::yield_now();
                 }
@@ -1595,10 +2458,7 @@ impl Uiua {
             recv
         };
         #[cfg(target_arch = "wasm32")]
-        let result = {
-            let mut env = make_env();
-            env.exec(f).map(|_| env.take_stack())
-        };
+        let state = WasmThread::Pending(Box::new(make_env()), f);
 
         let id = self.rt.thread.next_child_id;
         self.rt.thread.next_child_id += 1;
@@ -1608,11 +2468,8 @@ impl Uiua {
                 #[cfg(not(target_arch = "wasm32"))]
                 recv,
                 #[cfg(target_arch = "wasm32")]
-                result,
-                channel: Channel {
-                    send: this_send,
-                    recv: this_recv,
-                },
+                state,
+                channel: this_channel,
             },
         );
         self.push(id);
@@ -1640,7 +2497,8 @@ impl Uiua {
                 .children
                 .remove(&handle)
                 .ok_or_else(|| self.error("Invalid thread id"))?
-                .result?;
+                .state
+                .finish()?;
             match thread_stack.len() {
                 0 => self.push(Value::default()),
                 1 => self.push(thread_stack.into_iter().next().unwrap()),
@@ -1669,7 +2527,8 @@ impl Uiua {
                     .children
                     .remove(&handle)
                     .ok_or_else(|| self.error("Invalid thread id"))?
-                    .result?;
+                    .state
+                    .finish()?;
                 let row = if thread_stack.len() == 1 {
                     thread_stack.into_iter().next().unwrap()
                 } else {
@@ -1687,32 +2546,39 @@ impl Uiua {
         Ok(())
     }
     pub(crate) fn send(&self, id: Value, value: Value) -> UiuaResult {
-        if cfg!(target_arch = "wasm32") {
-            return Err(self.error("send is not supported in this environment"));
-        }
         let ids = id.as_natural_array(self, "Thread id must be an array of natural numbers")?;
         for id in ids.data {
+            #[cfg(not(target_arch = "wasm32"))]
             self.channel(id)?
                 .send
                 .send(value.clone())
                 .map_err(|_| self.error("Thread channel closed"))?;
+            #[cfg(target_arch = "wasm32")]
+            self.channel(id)?.send.lock().push_back(value.clone());
         }
         Ok(())
     }
     pub(crate) fn recv(&mut self, id: Value) -> UiuaResult {
-        if cfg!(target_arch = "wasm32") {
-            return Err(self.error("recv is not supported in this environment"));
-        }
         let ids = id.as_natural_array(self, "Thread id must be an array of natural numbers")?;
         let mut values = Vec::with_capacity(ids.data.len());
         for id in ids.data {
-            values.push(self.channel(id)?.recv.recv().map_err(|_| {
-                if let Err(e) = self.wait(id.into()) {
+            #[cfg(not(target_arch = "wasm32"))]
+            let value = self.channel(id)?.recv.recv().ok();
+            #[cfg(target_arch = "wasm32")]
+            let value = match self.recv_queued(id)? {
+                None if id == 0 => {
+                    return Err(self.error("The parent thread has not sent a value to receive"))
+                }
+                value => value,
+            };
+            let Some(value) = value else {
+                return Err(if let Err(e) = self.wait(id.into()) {
                     e
                 } else {
                     self.error("Thread channel closed")
-                }
-            })?);
+                });
+            };
+            values.push(value);
         }
         let mut val = Value::from_row_values(values, self)?;
         let mut shape = ids.shape;
@@ -1722,10 +2588,10 @@ impl Uiua {
         Ok(())
     }
     pub(crate) fn try_recv(&mut self, id: Value) -> UiuaResult {
-        if cfg!(target_arch = "wasm32") {
-            return Err(self.error("try_recv is not supported in this environment"));
-        }
         let id = id.as_nat(self, "Thread id must be a natural number")?;
+        #[cfg(target_arch = "wasm32")]
+        let value = (self.recv_queued(id)?).ok_or_else(|| self.error("No value available"))?;
+        #[cfg(not(target_arch = "wasm32"))]
         let value = match self.channel(id)?.recv.try_recv() {
             Ok(value) => value,
             Err(TryRecvError::Empty) => return Err(self.error("No value available")),
@@ -1740,6 +2606,17 @@ impl Uiua {
         self.push(value);
         Ok(())
     }
+    /// Receive a queued value, running the sending thread first if necessary
+    #[cfg(target_arch = "wasm32")]
+    fn recv_queued(&mut self, id: usize) -> UiuaResult<Option<Value>> {
+        if let Some(value) = self.channel(id)?.recv.lock().pop_front() {
+            return Ok(Some(value));
+        }
+        Ok(self.rt.thread.children.get_mut(&id).and_then(|thread| {
+            thread.state.run();
+            thread.channel.recv.lock().pop_front()
+        }))
+    }
     fn channel(&self, id: usize) -> UiuaResult<&Channel> {
         Ok(if id == 0 {
             self.rt
@@ -1815,3 +2692,16 @@ where
         self().arg_name()
     }
 }
+
+/// A 64-bit FNV-1a hash, which, unlike [`std::hash::DefaultHasher`],
+/// is the same across builds and platforms
+fn stable_hash(parts: &[&[u8]]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for part in parts {
+        for &byte in part.iter().chain(&[0xff]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
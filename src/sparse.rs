@@ -0,0 +1,202 @@
+//! Sparse storage for numeric arrays
+
+use std::{cmp::Ordering, mem::size_of};
+
+use crate::{algorithm::validate_size_impl, Array, Shape, Span, UiuaError, UiuaResult, Value};
+
+/// A numeric array that only stores its non-zero elements
+///
+/// Elements are stored in row-major order, and each index is stored at most once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseValue {
+    shape: Shape,
+    indices: Vec<Vec<usize>>,
+    data: Vec<f64>,
+}
+
+fn sparse_error(message: impl Into<String>) -> UiuaError {
+    UiuaError::new(message.into(), Span::Builtin)
+}
+
+impl Value {
+    /// Convert the value to a sparse array
+    ///
+    /// Only elements whose magnitude is greater than `threshold` are stored.
+    /// The value must be a number or byte array.
+    pub fn to_sparse(&self, threshold: f64) -> UiuaResult<SparseValue> {
+        let nums: Vec<f64> = match self {
+            Value::Num(arr) => arr.data.to_vec(),
+            Value::Byte(arr) => arr.data.iter().map(|&b| b as f64).collect(),
+            val => {
+                return Err(sparse_error(format!(
+                    "Cannot convert {} array to a sparse array",
+                    val.type_name()
+                )))
+            }
+        };
+        let shape = self.shape().clone();
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for (i, n) in nums.into_iter().enumerate() {
+            // NaN is always stored
+            if n.abs() > threshold || n.is_nan() {
+                indices.push(unflatten(i, &shape));
+                data.push(n);
+            }
+        }
+        Ok(SparseValue {
+            shape,
+            indices,
+            data,
+        })
+    }
+    /// Convert a sparse array to a dense value
+    ///
+    /// Elements that are not stored are `0`.
+    pub fn from_sparse(sparse: SparseValue) -> UiuaResult<Self> {
+        validate_size_impl(size_of::<f64>(), sparse.shape.iter().copied())
+            .map_err(|e| sparse_error(e.to_string()))?;
+        let mut data = vec![0.0; sparse.shape.elements()];
+        for (index, n) in sparse.indices.iter().zip(sparse.data) {
+            let i = flatten(index, &sparse.shape).ok_or_else(|| {
+                sparse_error(format!(
+                    "Index {index:?} is out of bounds of shape {}",
+                    sparse.shape
+                ))
+            })?;
+            data[i] = n;
+        }
+        Ok(Array::new(sparse.shape, data.as_slice()).into())
+    }
+}
+
+impl SparseValue {
+    /// Create a sparse array from its stored elements
+    ///
+    /// The elements may be given in any order.
+    /// Every index must be in bounds of the shape, and no index may be repeated.
+    pub fn new(shape: Shape, indices: Vec<Vec<usize>>, data: Vec<f64>) -> UiuaResult<Self> {
+        if indices.len() != data.len() {
+            return Err(sparse_error(format!(
+                "Sparse array has {} indices but {} elements",
+                indices.len(),
+                data.len()
+            )));
+        }
+        if let Some(index) = (indices.iter()).find(|index| flatten(index, &shape).is_none()) {
+            return Err(sparse_error(format!(
+                "Index {index:?} is out of bounds of shape {shape}"
+            )));
+        }
+        let mut pairs: Vec<_> = indices.into_iter().zip(data).collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if let Some(w) = pairs.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(sparse_error(format!(
+                "Index {:?} appears more than once in sparse array",
+                w[0].0
+            )));
+        }
+        let (indices, data) = pairs.into_iter().unzip();
+        Ok(SparseValue {
+            shape,
+            indices,
+            data,
+        })
+    }
+    /// Get the shape of the array
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+    /// Get the multidimensional index of each stored element
+    pub fn indices(&self) -> &[Vec<usize>] {
+        &self.indices
+    }
+    /// Get the stored elements
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+    /// Add two sparse arrays with the same shape
+    pub fn add(&self, other: &Self) -> UiuaResult<Self> {
+        self.zip(other, "add", |a, b| a + b)
+    }
+    /// Subtract a sparse array from another with the same shape
+    pub fn sub(&self, other: &Self) -> UiuaResult<Self> {
+        self.zip(other, "subtract", |a, b| a - b)
+    }
+    /// Multiply two sparse arrays with the same shape elementwise
+    pub fn mul(&self, other: &Self) -> UiuaResult<Self> {
+        self.zip(other, "multiply", |a, b| a * b)
+    }
+    /// Combine the elements of two sparse arrays
+    ///
+    /// Elements stored in only one array are combined with zero,
+    /// so results like `0 × ∞` are kept.
+    fn zip(&self, other: &Self, op: &str, f: impl Fn(f64, f64) -> f64) -> UiuaResult<Self> {
+        if self.shape != other.shape {
+            return Err(sparse_error(format!(
+                "Cannot {op} sparse arrays with shapes {} and {}",
+                self.shape, other.shape
+            )));
+        }
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        let mut push = |index: &Vec<usize>, n: f64| {
+            if n != 0.0 {
+                indices.push(index.clone());
+                data.push(n);
+            }
+        };
+        let (mut i, mut j) = (0, 0);
+        while i < self.data.len() && j < other.data.len() {
+            match self.indices[i].cmp(&other.indices[j]) {
+                Ordering::Equal => {
+                    push(&self.indices[i], f(self.data[i], other.data[j]));
+                    i += 1;
+                    j += 1;
+                }
+                Ordering::Less => {
+                    push(&self.indices[i], f(self.data[i], 0.0));
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    push(&other.indices[j], f(0.0, other.data[j]));
+                    j += 1;
+                }
+            }
+        }
+        for i in i..self.data.len() {
+            push(&self.indices[i], f(self.data[i], 0.0));
+        }
+        for j in j..other.data.len() {
+            push(&other.indices[j], f(0.0, other.data[j]));
+        }
+        Ok(SparseValue {
+            shape: self.shape.clone(),
+            indices,
+            data,
+        })
+    }
+}
+
+fn unflatten(mut i: usize, shape: &Shape) -> Vec<usize> {
+    let mut index = vec![0; shape.len()];
+    for (d, &dim) in shape.iter().enumerate().rev() {
+        index[d] = i % dim;
+        i /= dim;
+    }
+    index
+}
+
+fn flatten(index: &[usize], shape: &Shape) -> Option<usize> {
+    if index.len() != shape.len() {
+        return None;
+    }
+    let mut i = 0;
+    for (&j, &dim) in index.iter().zip(shape.iter()) {
+        if j >= dim {
+            return None;
+        }
+        i = i * dim + j;
+    }
+    Some(i)
+}
@@ -0,0 +1,214 @@
+use std::path::Component;
+
+use super::*;
+
+/// A system backend that restricts file access of another backend to a root directory
+///
+/// This is usually used to wrap `NativeSys`.
+///
+/// All file paths are resolved relative to the root. Absolute paths are treated as relative
+/// to the root, and paths that contain `..` are rejected. Symlinks are followed, and any path
+/// that resolves to a location outside the root is rejected.
+///
+/// Only the file system is confined. Standard IO, the terminal, the clipboard, audio, images, time, and
+/// sleeping are forwarded to the wrapped backend unchanged. Everything else is not supported,
+/// including environment variables, TCP and TLS sockets, HTTP requests, webcams, running
+/// commands, spawning processes, FFI, invoking paths, changing directories, and loading
+/// git modules.
+///
+/// Paths are checked when they are opened, so the root should not be modified concurrently
+/// by code outside the chroot.
+pub struct ChrootedNativeSys {
+    inner: Arc<dyn SysBackend>,
+    root: PathBuf,
+}
+
+impl ChrootedNativeSys {
+    /// Restrict a backend to a root directory
+    pub fn new(inner: impl IntoSysBackend, root: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: inner.into_sys_backend(),
+            root: root.into(),
+        }
+    }
+    /// Get the wrapped backend
+    pub fn inner(&self) -> &dyn SysBackend {
+        &*self.inner
+    }
+    /// Get the root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+    fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, String> {
+        let path = path.as_ref();
+        let escape = || format!("Path {} may not leave the root directory", path.display());
+        let root = (self.root.canonicalize())
+            .map_err(|e| format!("Invalid root directory {}: {e}", self.root.display()))?;
+        let mut resolved = root.clone();
+        for comp in path.components() {
+            match comp {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+                Component::ParentDir => return Err(escape()),
+            }
+        }
+        // Resolve symlinks in the deepest existing ancestor
+        let mut existing = resolved.as_path();
+        let mut missing = Vec::new();
+        let mut canon = loop {
+            if let Ok(canon) = existing.canonicalize() {
+                break canon;
+            }
+            // A dangling symlink could be created through
+            if existing.symlink_metadata().is_ok() {
+                return Err(escape());
+            }
+            missing.push(existing.file_name().ok_or_else(escape)?);
+            existing = existing.parent().ok_or_else(escape)?;
+        };
+        canon.extend(missing.into_iter().rev());
+        if !canon.starts_with(&root) {
+            return Err(escape());
+        }
+        Ok(canon)
+    }
+}
+
+impl SysBackend for ChrootedNativeSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn save_error_color(&self, message: String, colored: String) {
+        self.inner.save_error_color(message, colored)
+    }
+    fn output_enabled(&self) -> bool {
+        self.inner.output_enabled()
+    }
+    fn set_output_enabled(&self, enabled: bool) -> bool {
+        self.inner.set_output_enabled(enabled)
+    }
+    fn print_str_stdout(&self, s: &str) -> Result<(), String> {
+        self.inner.print_str_stdout(s)
+    }
+    fn print_str_stderr(&self, s: &str) -> Result<(), String> {
+        self.inner.print_str_stderr(s)
+    }
+    fn print_str_trace(&self, s: &str) {
+        self.inner.print_str_trace(s)
+    }
+    fn show(&self, value: Value) -> Result<(), String> {
+        self.inner.show(value)
+    }
+    fn scan_line_stdin(&self) -> Result<Option<String>, String> {
+        self.inner.scan_line_stdin()
+    }
+    fn scan_stdin(&self, count: Option<usize>) -> Result<Vec<u8>, String> {
+        self.inner.scan_stdin(count)
+    }
+    fn scan_until_stdin(&self, delim: &[u8]) -> Result<Vec<u8>, String> {
+        self.inner.scan_until_stdin(delim)
+    }
+    fn set_raw_mode(&self, raw_mode: bool) -> Result<(), String> {
+        self.inner.set_raw_mode(raw_mode)
+    }
+    fn get_raw_mode(&self) -> Result<bool, String> {
+        self.inner.get_raw_mode()
+    }
+    fn term_size(&self) -> Result<(usize, usize), String> {
+        self.inner.term_size()
+    }
+    fn exit(&self, status: i32) -> Result<(), String> {
+        self.inner.exit(status)
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        (self.resolve(path)).is_ok_and(|path| self.inner.file_exists(&path.to_string_lossy()))
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        self.inner.list_dir(&self.resolve(path)?.to_string_lossy())
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        self.inner.is_file(&self.resolve(path)?.to_string_lossy())
+    }
+    fn delete(&self, path: &str) -> Result<(), String> {
+        self.inner.delete(&self.resolve(path)?.to_string_lossy())
+    }
+    fn trash(&self, path: &str) -> Result<(), String> {
+        self.inner.trash(&self.resolve(path)?.to_string_lossy())
+    }
+    fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
+        self.inner.read(handle, count)
+    }
+    fn read_all(&self, handle: Handle) -> Result<Vec<u8>, String> {
+        self.inner.read_all(handle)
+    }
+    fn read_until(&self, handle: Handle, delim: &[u8]) -> Result<Vec<u8>, String> {
+        self.inner.read_until(handle, delim)
+    }
+    fn read_lines<'a>(&self, handle: Handle) -> Result<ReadLinesReturnFn<'a>, String> {
+        self.inner.read_lines(handle)
+    }
+    fn write(&self, handle: Handle, contents: &[u8]) -> Result<(), String> {
+        self.inner.write(handle, contents)
+    }
+    fn create_file(&self, path: &Path) -> Result<Handle, String> {
+        self.inner.create_file(&self.resolve(path)?)
+    }
+    fn open_file(&self, path: &Path, write: bool) -> Result<Handle, String> {
+        self.inner.open_file(&self.resolve(path)?, write)
+    }
+    fn make_dir(&self, path: &Path) -> Result<(), String> {
+        self.inner.make_dir(&self.resolve(path)?)
+    }
+    fn file_read_all(&self, path: &Path) -> Result<Vec<u8>, String> {
+        self.inner.file_read_all(&self.resolve(path)?)
+    }
+    fn file_write_all(&self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        self.inner.file_write_all(&self.resolve(path)?, contents)
+    }
+    fn clipboard(&self) -> Result<String, String> {
+        self.inner.clipboard()
+    }
+    fn set_clipboard(&self, contents: &str) -> Result<(), String> {
+        self.inner.set_clipboard(contents)
+    }
+    fn sleep(&self, seconds: f64) -> Result<(), String> {
+        self.inner.sleep(seconds)
+    }
+    fn sleep_precise(&self, seconds: f64) -> Result<(), String> {
+        self.inner.sleep_precise(seconds)
+    }
+    fn allow_thread_spawning(&self) -> bool {
+        self.inner.allow_thread_spawning()
+    }
+    #[cfg(feature = "image")]
+    fn show_image(&self, image: DynamicImage, label: Option<&str>) -> Result<(), String> {
+        self.inner.show_image(image, label)
+    }
+    fn show_gif(&self, gif_bytes: Vec<u8>, label: Option<&str>) -> Result<(), String> {
+        self.inner.show_gif(gif_bytes, label)
+    }
+    fn play_audio(&self, wave_bytes: Vec<u8>, label: Option<&str>) -> Result<(), String> {
+        self.inner.play_audio(wave_bytes, label)
+    }
+    fn audio_sample_rate(&self) -> u32 {
+        self.inner.audio_sample_rate()
+    }
+    fn stream_audio(&self, f: AudioStreamFn) -> Result<(), String> {
+        self.inner.stream_audio(f)
+    }
+    fn now(&self) -> f64 {
+        self.inner.now()
+    }
+    fn close(&self, handle: Handle) -> Result<(), String> {
+        self.inner.close(handle)
+    }
+    fn timezone(&self) -> Result<f64, String> {
+        self.inner.timezone()
+    }
+    fn breakpoint(&self, env: &Uiua) -> Result<bool, String> {
+        self.inner.breakpoint(env)
+    }
+}
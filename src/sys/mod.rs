@@ -1,3 +1,4 @@
+mod chroot;
 #[cfg(feature = "native_sys")]
 pub(crate) mod native;
 
@@ -11,6 +12,7 @@ use std::{
     time::Duration,
 };
 
+use ecow::EcoVec;
 use enum_iterator::{all, Sequence};
 #[cfg(feature = "image")]
 use image::DynamicImage;
@@ -19,6 +21,7 @@ use parking_lot::Mutex;
 use serde::*;
 use time::UtcOffset;
 
+pub use self::chroot::*;
 #[cfg(feature = "native_sys")]
 pub use self::native::*;
 use crate::{
@@ -275,6 +278,13 @@ sys_op! {
     /// Using [&cl] on *all 3* handles will kill the child process.
     /// [under][&runs] calls [&cl] on all 3 streams automatically.
     (1(3), RunStream, Command, "&runs", "run command stream", Mutating),
+    /// Run a command with some input and wait for it to finish
+    ///
+    /// Expects a command and a string or list of bytes to write to the command's stdin.
+    /// The command may be a string, a rank `2` character array, or a rank `1` array of [box] strings.
+    /// The exit code, stdout bytes, and stderr bytes will each be pushed to the stack.
+    /// If the command was killed by a signal, the exit code is `128` plus the signal number.
+    (2(3), SpawnProcess, Command, "&runp", "run command piped", Mutating),
     /// Change the current directory
     (1(0), ChangeDirectory, Filesystem, "&cd", "change directory", Mutating),
     /// Get the contents of the clipboard
@@ -290,6 +300,12 @@ sys_op! {
     /// On the web, this example will hang for 1 second.
     /// ex: ⚂ &sl 1
     (1(0), Sleep, Misc, "&sl", "sleep", Mutating),
+    /// Sleep for n seconds with sub-millisecond precision
+    ///
+    /// The native backend uses the platform's high-resolution timers where they are available.
+    /// Otherwise, this may use more CPU time than [&sl], so it should only be used for short delays.
+    /// ex: ⚂ &slp 0.0005
+    (1(0), SleepPrecise, Misc, "&slp", "sleep precisely", Mutating),
     /// Read characters formed by at most n bytes from a stream
     ///
     /// Expects a count and a stream handle.
@@ -543,6 +559,16 @@ sys_op! {
     (2(0), TcpSetWriteTimeout, Tcp, "&tcpswt", "tcp - set write timeout", Mutating),
     /// Get the connection address of a TCP socket
     (1, TcpAddr, Tcp, "&tcpaddr", "tcp - address", Mutating),
+    /// Make an HTTP request
+    ///
+    /// Expects a method, a URL, headers, and a body.
+    /// Headers are given as a rank `2` array of [box] strings where each row is a name and a value. An empty list means no headers.
+    /// The body may be a string or a list of bytes. An empty body sends no body.
+    /// Returns the status code, the response headers in the same format, and the response body as bytes.
+    /// A header that appears multiple times in the response has a row for each value.
+    ///
+    /// Responses with error status codes are returned normally rather than causing an error.
+    (4(3), HttpRequest, Tcp, "&http", "http - request", Mutating),
     /// Capture an image from a webcam
     ///
     /// Takes the index of the webcam to capture from.
@@ -936,6 +962,12 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn sleep(&self, seconds: f64) -> Result<(), String> {
         Err("Sleeping is not supported in this environment".into())
     }
+    /// Sleep the current thread for `seconds` seconds with sub-millisecond precision
+    ///
+    /// Defaults to [`SysBackend::sleep`]
+    fn sleep_precise(&self, seconds: f64) -> Result<(), String> {
+        self.sleep(seconds)
+    }
     /// Whether thread spawning is allowed
     fn allow_thread_spawning(&self) -> bool {
         false
@@ -1015,6 +1047,16 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn close(&self, handle: Handle) -> Result<(), String> {
         Ok(())
     }
+    /// Make an HTTP request
+    fn http_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        body: Option<&[u8]>,
+    ) -> Result<HttpResponse, String> {
+        Err("HTTP requests are not supported in this environment".into())
+    }
     /// Invoke a path with the system's default program
     fn invoke(&self, path: &str) -> Result<(), String> {
         Err("Invoking paths is not supported in this environment".into())
@@ -1035,6 +1077,15 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<[Handle; 3], String> {
         Err("Running streamed commands is not supported in this environment".into())
     }
+    /// Run a command with optional input to its stdin, capturing its output
+    fn spawn_process(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<Vec<u8>>,
+    ) -> Result<ProcessOutput, String> {
+        Err("Spawning processes is not supported in this environment".into())
+    }
     /// Change the current directory
     fn change_directory(&self, path: &str) -> Result<(), String> {
         Err("Changing directories is not supported in this environment".into())
@@ -1100,6 +1151,30 @@ pub enum GitTarget {
     Commit(String),
 }
 
+/// A response to an HTTP request
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponse {
+    /// The status code
+    pub status: u16,
+    /// The header names and values
+    ///
+    /// A header that appears multiple times has an entry for each value.
+    pub headers: Vec<(String, String)>,
+    /// The body
+    pub body: Vec<u8>,
+}
+
+/// The output of a process run with [`SysBackend::spawn_process`]
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOutput {
+    /// The captured stdout
+    pub stdout: Vec<u8>,
+    /// The captured stderr
+    pub stderr: Vec<u8>,
+    /// The exit code, or `128` plus the signal number if the process was killed by a signal
+    pub exit_code: i32,
+}
+
 impl fmt::Debug for dyn SysBackend {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "<sys backend>")
@@ -1178,7 +1253,16 @@ impl IntoSysBackend for Arc<dyn SysBackend> {
 }
 
 impl SysOp {
+    fn respect_io_rate_limit(&self, env: &Uiua) -> UiuaResult {
+        match self.class() {
+            SysOpClass::Filesystem | SysOpClass::Stream | SysOpClass::Tcp | SysOpClass::Command => {
+                env.respect_io_rate_limit()
+            }
+            _ => Ok(()),
+        }
+    }
     pub(crate) fn run(&self, env: &mut Uiua) -> UiuaResult {
+        self.respect_io_rate_limit(env)?;
         match self {
             SysOp::Show => {
                 let val = env.pop(1)?;
@@ -1589,7 +1673,7 @@ impl SysOp {
                 let contents = env.rt.backend.clipboard().map_err(|e| env.error(e))?;
                 env.push(contents);
             }
-            SysOp::Sleep => {
+            SysOp::Sleep | SysOp::SleepPrecise => {
                 let mut seconds = env.pop(1)?.as_num(env, "Sleep time must be a number")?;
                 if seconds < 0.0 {
                     return Err(env.error("Sleep time must be positive"));
@@ -1602,7 +1686,11 @@ impl SysOp {
                     let max = limit - elapsed;
                     seconds = seconds.min(max);
                 }
-                env.rt.backend.sleep(seconds).map_err(|e| env.error(e))?;
+                if let SysOp::SleepPrecise = self {
+                    (env.rt.backend.sleep_precise(seconds)).map_err(|e| env.error(e))?;
+                } else {
+                    env.rt.backend.sleep(seconds).map_err(|e| env.error(e))?;
+                }
             }
             SysOp::TcpListen => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
@@ -1657,6 +1745,29 @@ impl SysOp {
                 let handle = handle.value(HandleKind::TlsSocket(sock_addr));
                 env.push(handle);
             }
+            SysOp::HttpRequest => {
+                let method = env.pop(1)?.as_string(env, "Method must be a string")?;
+                let url = env.pop(2)?.as_string(env, "URL must be a string")?;
+                let headers = value_to_http_headers(&env.pop(3)?, env)?;
+                let body: Vec<u8> = match env.pop(4)? {
+                    Value::Num(arr) => arr.data.iter().map(|&x| x as u8).collect(),
+                    Value::Byte(arr) => arr.data.into(),
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into(),
+                    Value::Complex(_) => return Err(env.error("HTTP body cannot be complex")),
+                    Value::Box(_) => return Err(env.error("HTTP body cannot be boxed")),
+                };
+                let body = (!body.is_empty()).then_some(body.as_slice());
+                let res = (env.rt.backend)
+                    .http_request(&method, &url, &headers, body)
+                    .map_err(|e| env.error(e))?;
+                let header_data: EcoVec<Boxed> = (res.headers.into_iter())
+                    .flat_map(|(name, value)| [Boxed(name.into()), Boxed(value.into())])
+                    .collect();
+                let header_count = header_data.len() / 2;
+                env.push(Array::<u8>::from(res.body.as_slice()));
+                env.push(Array::new([header_count, 2], header_data));
+                env.push(res.status as f64);
+            }
             SysOp::TcpAddr => {
                 let handle = env.pop(1)?.as_handle(env, None)?;
                 let addr = env.rt.backend.tcp_addr(handle).map_err(|e| env.error(e))?;
@@ -1732,6 +1843,19 @@ impl SysOp {
                     env.push(handle.value(kind(command.clone())));
                 }
             }
+            SysOp::SpawnProcess => {
+                let (command, args) = value_to_command(&env.pop(1)?, env)?;
+                let input = match env.pop(2)? {
+                    Value::Char(arr) => arr.data.iter().collect::<String>().into_bytes(),
+                    val => val.as_bytes(env, "Process input must be a string or bytes")?,
+                };
+                let output = (env.rt.backend)
+                    .spawn_process(&command, &args, Some(input))
+                    .map_err(|e| env.error(e))?;
+                env.push(output.stderr);
+                env.push(output.stdout);
+                env.push(output.exit_code);
+            }
             SysOp::ChangeDirectory => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 (env.rt.backend)
@@ -1842,6 +1966,7 @@ impl SysOp {
         Ok(())
     }
     pub(crate) fn run_mod(&self, ops: Ops, env: &mut Uiua) -> UiuaResult {
+        self.respect_io_rate_limit(env)?;
         match self {
             SysOp::ReadLines => {
                 let [f] = get_ops(ops, env)?;
@@ -1947,6 +2072,26 @@ impl SysOp {
     }
 }
 
+fn value_to_http_headers(value: &Value, env: &Uiua) -> UiuaResult<Vec<(String, String)>> {
+    if value.row_count() == 0 {
+        return Ok(Vec::new());
+    }
+    let error = || env.error("HTTP headers must be a rank 2 array of boxed string pairs");
+    let Value::Box(arr) = value else {
+        return Err(error());
+    };
+    if arr.rank() != 2 || arr.shape[1] != 2 {
+        return Err(error());
+    }
+    let string = |bx: &Boxed| match bx.as_value() {
+        Value::Char(arr) if arr.rank() <= 1 => Ok(arr.data.iter().collect::<String>()),
+        _ => Err(error()),
+    };
+    (arr.data.chunks_exact(2))
+        .map(|pair| Ok((string(&pair[0])?, string(&pair[1])?)))
+        .collect()
+}
+
 fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String>)> {
     let mut strings = Vec::new();
     match value {
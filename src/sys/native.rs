@@ -2,16 +2,16 @@ use std::{
     any::Any,
     env::{self, set_current_dir},
     fs::{self, File, OpenOptions},
-    io::{stderr, stdin, stdout, BufRead, BufReader, Read, Write},
+    io::{self, stderr, stdin, stdout, BufRead, BufReader, Read, Write},
     net::*,
     path::{Path, PathBuf},
-    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Stdio},
     slice,
     sync::{
         atomic::{self, AtomicBool, AtomicU64},
         Arc,
     },
-    thread::sleep,
+    thread::{self, sleep},
     time::Duration,
 };
 
@@ -21,8 +21,11 @@ use crossbeam_channel as channel;
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
 
+#[cfg(feature = "http")]
+use crate::HttpResponse;
 use crate::{
-    terminal_size, GitTarget, Handle, ReadLinesFn, ReadLinesReturnFn, Span, SysBackend, Uiua, Value,
+    terminal_size, GitTarget, Handle, ProcessOutput, ReadLinesFn, ReadLinesReturnFn, Span,
+    SysBackend, Uiua, Value,
 };
 
 /// The default native system backend
@@ -294,6 +297,119 @@ pub fn set_audio_stream_time_port(port: u16) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Sleep using an absolute deadline on the monotonic clock,
+/// which does not accumulate the overshoot of relative sleeps
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn sleep_precise(duration: Duration) -> Result<(), String> {
+    let mut deadline = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut deadline) } != 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    let nanos = deadline.tv_nsec as u64 + duration.subsec_nanos() as u64;
+    let secs = duration.as_secs().saturating_add(nanos / 1_000_000_000);
+    deadline.tv_sec =
+        (deadline.tv_sec).saturating_add(secs.try_into().unwrap_or(libc::time_t::MAX));
+    deadline.tv_nsec = (nanos % 1_000_000_000) as _;
+    loop {
+        let code = unsafe {
+            libc::clock_nanosleep(
+                libc::CLOCK_MONOTONIC,
+                libc::TIMER_ABSTIME,
+                &deadline,
+                std::ptr::null_mut(),
+            )
+        };
+        match code {
+            0 => return Ok(()),
+            libc::EINTR => continue,
+            code => return Err(io::Error::from_raw_os_error(code).to_string()),
+        }
+    }
+}
+
+/// Sleep using a high-resolution waitable timer
+///
+/// These are available since Windows 10 version 1803.
+/// On older versions, the system timer resolution is raised for the duration of the sleep instead.
+#[cfg(windows)]
+fn sleep_precise(duration: Duration) -> Result<(), String> {
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, WAIT_OBJECT_0},
+        Media::{timeBeginPeriod, timeEndPeriod},
+        System::Threading::{
+            CreateWaitableTimerExW, SetWaitableTimer, WaitForSingleObject,
+            CREATE_WAITABLE_TIMER_HIGH_RESOLUTION, INFINITE, TIMER_ALL_ACCESS,
+        },
+    };
+    // Negative due times are relative, in units of 100 nanoseconds
+    let due = -i64::try_from(duration.as_nanos() / 100).unwrap_or(i64::MAX);
+    unsafe {
+        let timer = CreateWaitableTimerExW(
+            null(),
+            null(),
+            CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+            TIMER_ALL_ACCESS,
+        );
+        if timer.is_null() {
+            timeBeginPeriod(1);
+            sleep(duration);
+            timeEndPeriod(1);
+            return Ok(());
+        }
+        let res = if SetWaitableTimer(timer, &due, 0, None, null_mut(), 0) != 0
+            && WaitForSingleObject(timer, INFINITE) == WAIT_OBJECT_0
+        {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error().to_string())
+        };
+        CloseHandle(timer);
+        res
+    }
+}
+
+/// Sleep for most of the duration and spin for the rest,
+/// as the OS timer may overshoot by a few milliseconds
+#[cfg(not(any(
+    windows,
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd"
+)))]
+fn sleep_precise(duration: Duration) -> Result<(), String> {
+    const SPIN: Duration = Duration::from_millis(2);
+    let deadline = std::time::Instant::now() + duration;
+    if let Some(coarse) = duration.checked_sub(SPIN) {
+        sleep(coarse);
+    }
+    while std::time::Instant::now() < deadline {
+        thread::yield_now();
+    }
+    Ok(())
+}
+
+/// Get the exit code of a finished process
+///
+/// A process killed by a signal has no exit code, so it gets
+/// `128` plus the signal number, as in most shells.
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    -1
+}
+
 impl SysBackend for NativeSys {
     fn any(&self) -> &dyn Any {
         self
@@ -628,6 +744,9 @@ impl SysBackend for NativeSys {
         sleep(Duration::from_secs_f64(seconds));
         Ok(())
     }
+    fn sleep_precise(&self, seconds: f64) -> Result<(), String> {
+        sleep_precise(Duration::from_secs_f64(seconds))
+    }
     fn allow_thread_spawning(&self) -> bool {
         true
     }
@@ -952,6 +1071,45 @@ impl SysBackend for NativeSys {
             Err("Invalid stream handle".to_string())
         }
     }
+    #[cfg(feature = "http")]
+    fn http_request(
+        &self,
+        method: &str,
+        url: &str,
+        headers: &[(String, String)],
+        body: Option<&[u8]>,
+    ) -> Result<HttpResponse, String> {
+        let mut req = ureq::request(method, url);
+        for (name, value) in headers {
+            req = req.set(name, value);
+        }
+        let res = match body {
+            Some(body) => req.send_bytes(body),
+            None => req.call(),
+        };
+        let res = match res {
+            Ok(res) | Err(ureq::Error::Status(_, res)) => res,
+            Err(e) => return Err(e.to_string()),
+        };
+        let status = res.status();
+        let mut names = res.headers_names();
+        names.dedup();
+        let headers = (names.into_iter())
+            .flat_map(|name| {
+                let values: Vec<String> = res.all(&name).into_iter().map(Into::into).collect();
+                values.into_iter().map(move |value| (name.clone(), value))
+            })
+            .collect();
+        let mut body = Vec::new();
+        (res.into_reader())
+            .read_to_end(&mut body)
+            .map_err(|e| e.to_string())?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
     #[cfg(feature = "invoke")]
     fn invoke(&self, path: &str) -> Result<(), String> {
         open::that(path).map_err(|e| e.to_string())
@@ -963,7 +1121,7 @@ impl SysBackend for NativeSys {
             .map_err(|e| e.to_string())?
             .wait()
             .map_err(|e| e.to_string())?;
-        Ok(status.code().unwrap_or(0))
+        Ok(exit_code(status))
     }
     fn run_command_capture(
         &self,
@@ -975,11 +1133,48 @@ impl SysBackend for NativeSys {
             .output()
             .map_err(|e| e.to_string())?;
         Ok((
-            output.status.code().unwrap_or(0),
+            exit_code(output.status),
             String::from_utf8_lossy(&output.stdout).into(),
             String::from_utf8_lossy(&output.stderr).into(),
         ))
     }
+    fn spawn_process(
+        &self,
+        program: &str,
+        args: &[String],
+        stdin: Option<Vec<u8>>,
+    ) -> Result<ProcessOutput, String> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(if stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        // Write input on another thread so a child that fills its
+        // output pipes before reading all its input doesn't deadlock
+        let writer = (child.stdin.take().zip(stdin))
+            .map(|(mut pipe, input)| thread::spawn(move || pipe.write_all(&input)));
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if let Some(writer) = writer {
+            match writer.join() {
+                Ok(Ok(())) => {}
+                // The child may exit without reading all its input
+                Ok(Err(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+                Ok(Err(e)) => return Err(e.to_string()),
+                Err(_) => return Err("Failed to write to process stdin".into()),
+            }
+        }
+        Ok(ProcessOutput {
+            stdout: output.stdout,
+            stderr: output.stderr,
+            exit_code: exit_code(output.status),
+        })
+    }
     fn run_command_stream(&self, command: &str, args: &[&str]) -> Result<[Handle; 3], String> {
         let mut child = Command::new(command)
             .args(args)
@@ -16,7 +16,8 @@ use serde::*;
 use crate::{
     check::SigCheckError,
     compile::invert::{InversionError, InversionResult},
-    Assembly, BindingKind, DynamicFunction, Function, ImplPrimitive, Primitive, Signature, Value,
+    Assembly, BindingKind, DynamicFunction, ElementType, Function, FunctionId, ImplPrimitive,
+    Primitive, Signature, Value,
 };
 
 node!(
@@ -58,7 +59,7 @@ node!(
     /// Set some values for an output comment
     SetOutputComment { i: usize, n: usize },
     /// Validate that a value has a certain type
-    ValidateType { index: usize, type_num: u8, name: EcoString, span: usize },
+    ValidateType { index: usize, ty: ElementType, name: EcoString, span: usize },
     /// Call a Rust function
     Dynamic(func(DynamicFunction)),
     /// Push some values to the under stack
@@ -71,6 +72,8 @@ node!(
     NoInline(inner(Arc<Node>)),
     /// Track the caller of this node
     TrackCaller(inner(Arc<Node>)),
+    /// A node that is kept in the tree but never run
+    CommentedOut(inner(Arc<Node>)),
     /// Bind a local value
     WithLocal { def: usize, inner: Arc<SigNode>, span: usize },
     /// Get a local value
@@ -79,6 +82,8 @@ node!(
     SetLocal { def: usize, span: usize },
     /// Normalize a struct-of-arrays data def
     NormalizeSoA { len_index: usize, mask: u64, span: usize },
+    /// Repeat a function a constant number of times
+    Repeat { count: usize, inner: Arc<SigNode>, span: usize },
     /// Push a value onto the stack
     (#[serde(untagged)] rep),
     Push(val(Value)),
@@ -297,6 +302,13 @@ impl CustomInverse {
             .chain(self.anti.as_ref())
             .chain(self.under.as_ref().into_iter().flat_map(|(b, a)| [a, b]))
     }
+    /// Iterate mutably over all nodes
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut SigNode> {
+        (self.normal.as_mut().into_iter())
+            .chain(self.un.as_mut())
+            .chain(self.anti.as_mut())
+            .chain(self.under.as_mut().into_iter().flat_map(|(b, a)| [a, b]))
+    }
 }
 
 impl Default for Node {
@@ -704,8 +716,8 @@ impl fmt::Debug for Node {
                 count, unbox: true, ..
             } => write!(f, "<unpack (unbox) {count}>"),
             Node::SetOutputComment { i, n, .. } => write!(f, "<set output comment {i}({n})>"),
-            Node::ValidateType { type_num, name, .. } => {
-                write!(f, "<validate {name} as {type_num}>")
+            Node::ValidateType { ty, name, .. } => {
+                write!(f, "<validate {name} as {ty}>")
             }
             Node::Dynamic(func) => write!(f, "<dynamic function {}>", func.index),
             Node::PushUnder(count, _) => write!(f, "push-u-{count}"),
@@ -715,6 +727,10 @@ impl fmt::Debug for Node {
             Node::TrackCaller(inner) => {
                 f.debug_tuple("track-caller").field(inner.as_ref()).finish()
             }
+            Node::CommentedOut(inner) => f
+                .debug_tuple("commented-out")
+                .field(inner.as_ref())
+                .finish(),
             Node::WithLocal { def: id, inner, .. } => {
                 write!(f, "bind-local {id} (")?;
                 inner.fmt(f)?;
@@ -725,6 +741,128 @@ impl fmt::Debug for Node {
             Node::NormalizeSoA {
                 len_index, mask, ..
             } => write!(f, "normalize-soa({len_index}, {mask})"),
+            Node::Repeat { count, inner, .. } => {
+                write!(f, "repeat {count} (")?;
+                inner.fmt(f)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Node {
+    /// Write a human-readable approximation of this node's source code
+    pub(crate) fn write_source(&self, asm: &Assembly, s: &mut String) {
+        fn write_arg(sn: &SigNode, asm: &Assembly, s: &mut String) {
+            if sn.node.len() == 1 {
+                sn.node.write_source(asm, s);
+            } else {
+                s.push('(');
+                sn.node.write_source(asm, s);
+                s.push(')');
+            }
+        }
+        fn binding_name(index: usize, asm: &Assembly) -> String {
+            (asm.bindings.get(index))
+                .and_then(|binding| binding.span.try_as_str(&asm.inputs, |s| s.to_string()))
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("<global {index}>"))
+        }
+        match self {
+            Node::Run(nodes) => {
+                // Nodes are stored in execution order, which is the reverse of source order
+                let mut first = true;
+                for node in nodes.iter().rev() {
+                    if matches!(node, Node::SetOutputComment { .. }) {
+                        continue;
+                    }
+                    if !first {
+                        s.push(' ');
+                    }
+                    first = false;
+                    node.write_source(asm, s);
+                }
+            }
+            Node::Push(val) => s.push_str(&val.representation()),
+            Node::Prim(prim, _) => s.push_str(&prim.to_string()),
+            Node::ImplPrim(prim, _) => s.push_str(&prim.to_string()),
+            Node::Mod(prim, args, _) => {
+                s.push_str(&prim.to_string());
+                for arg in args {
+                    write_arg(arg, asm, s);
+                }
+            }
+            Node::ImplMod(prim, args, _) => {
+                s.push_str(&prim.to_string());
+                for arg in args {
+                    write_arg(arg, asm, s);
+                }
+            }
+            Node::Array { inner, boxed, .. } => {
+                s.push(if *boxed { '{' } else { '[' });
+                inner.write_source(asm, s);
+                s.push(if *boxed { '}' } else { ']' });
+            }
+            Node::Call(func, _) => match &func.id {
+                FunctionId::Named(name) => s.push_str(name),
+                _ => {
+                    s.push('(');
+                    asm[func].write_source(asm, s);
+                    s.push(')');
+                }
+            },
+            Node::CallGlobal(index, _) | Node::CallMacro { index, .. } => {
+                s.push_str(&binding_name(*index, asm))
+            }
+            Node::BindGlobal { index, .. } => {
+                s.push_str(&binding_name(*index, asm));
+                s.push_str(" ←");
+            }
+            Node::Label(label, _) => {
+                s.push('$');
+                s.push_str(label);
+            }
+            Node::RemoveLabel(..) => s.push_str("°$"),
+            Node::Switch { branches, .. } => {
+                s.push_str(&Primitive::Switch.to_string());
+                s.push('(');
+                for (i, branch) in branches.iter().enumerate() {
+                    if i > 0 {
+                        s.push('|');
+                    }
+                    branch.node.write_source(asm, s);
+                }
+                s.push(')');
+            }
+            Node::CustomInverse(cust, _) => match &cust.normal {
+                Ok(normal) => {
+                    s.push('(');
+                    normal.node.write_source(asm, s);
+                    s.push(')');
+                }
+                Err(_) => s.push_str(&format!("{self:?}")),
+            },
+            Node::Unpack {
+                prim: Some(prim), ..
+            } => s.push_str(&format!("{}{prim}", Primitive::Un)),
+            Node::Unpack { unbox, .. } => {
+                s.push_str(&Primitive::Un.to_string());
+                s.push_str(if *unbox { "{}" } else { "[]" });
+            }
+            Node::SetOutputComment { .. } => {}
+            Node::NoInline(inner) | Node::TrackCaller(inner) => inner.write_source(asm, s),
+            Node::CommentedOut(inner) => {
+                s.push_str("# ");
+                inner.write_source(asm, s);
+                s.push('\n');
+            }
+            Node::WithLocal { inner, .. } => inner.node.write_source(asm, s),
+            Node::Repeat { count, inner, .. } => {
+                s.push_str(&Primitive::Repeat.to_string());
+                write_arg(inner, asm, s);
+                s.push_str(&format!(" {count}"));
+            }
+            node => s.push_str(&format!("{node:?}")),
         }
     }
 }
@@ -789,7 +927,9 @@ impl Node {
                 Node::CustomInverse(cust, _) => (cust.normal.as_ref().ok())
                     .or(cust.un.as_ref())
                     .is_some_and(|sn| recurse(&sn.node, purity, asm, visited)),
-                Node::WithLocal { inner, .. } => recurse(&inner.node, purity, asm, visited),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, purity, asm, visited)
+                }
                 _ => true,
             };
             visited.truncate(len);
@@ -833,7 +973,9 @@ impl Node {
                 Node::CustomInverse(cust, _) => (cust.normal.as_ref().ok())
                     .or(cust.un.as_ref())
                     .is_some_and(|sn| recurse(&sn.node, asm, visited)),
-                Node::WithLocal { inner, .. } => recurse(&inner.node, asm, visited),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, asm, visited)
+                }
                 _ => true,
             };
             visited.truncate(len);
@@ -862,7 +1004,9 @@ impl Node {
                     .or(cust.un.as_ref())
                     .is_some_and(|sn| recurse(&sn.node, asm, visited)),
                 Node::Array { inner, .. } => recurse(inner, asm, visited),
-                Node::WithLocal { inner, .. } => recurse(&inner.node, asm, visited),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, asm, visited)
+                }
                 _ => false,
             };
             visited.truncate(len);
@@ -903,7 +1047,9 @@ impl Node {
                     .iter()
                     .find_map(|br| recurse(&br.node, asm, spans, visited)),
                 Node::Array { inner, .. } => recurse(inner, asm, spans, visited),
-                Node::WithLocal { inner, .. } => recurse(&inner.node, asm, spans, visited),
+                Node::WithLocal { inner, .. } | Node::Repeat { inner, .. } => {
+                    recurse(&inner.node, asm, spans, visited)
+                }
                 _ => None,
             };
             visited.truncate(len);
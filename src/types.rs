@@ -340,6 +340,7 @@ impl TypeRt<'_> {
                 _ => return Err(TypeError::NotSupported),
             },
             Node::NoInline(inner) | Node::TrackCaller(inner) => self.node(inner)?,
+            Node::CommentedOut(_) => {}
             &Node::PushUnder(n, _) => {
                 for _ in 0..n {
                     let value = self.pop()?;
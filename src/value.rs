@@ -5,17 +5,21 @@ use std::{
     hash::{Hash, Hasher},
     iter::once,
     mem::{size_of, take},
+    str::{self, FromStr},
 };
 
 use ecow::{EcoString, EcoVec};
+use rand::{seq::index, Rng};
 use serde::*;
 
 use crate::{
-    algorithm::{map::MapKeys, pervade::*, ErrorContext, FillContext},
+    algorithm::{
+        fill_value_shapes, map::MapKeys, pervade::*, validate_size_impl, ErrorContext, FillContext,
+    },
     array::*,
     cowslice::CowSlice,
     grid_fmt::GridFmt,
-    Boxed, Complex, Shape, Uiua, UiuaResult,
+    Boxed, Complex, Shape, Span, Uiua, UiuaError, UiuaResult,
 };
 
 /// A generic array value
@@ -43,6 +47,94 @@ impl Default for Value {
     }
 }
 
+/// The type of the elements of a [`Value`]
+///
+/// The discriminants match the numbers returned by `type`.
+///
+/// Element types are ordered by widening rather than by discriminant:
+/// numbers < complex numbers < characters < boxes.
+/// Values of different types are ordered by their element type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ElementType {
+    /// Real numbers
+    Num,
+    /// Characters
+    Char,
+    /// Boxes
+    Box,
+    /// Complex numbers
+    Complex,
+}
+
+impl ElementType {
+    /// Get the name of the type
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Num => "number",
+            Self::Complex => "complex",
+            Self::Char => "character",
+            Self::Box => "box",
+        }
+    }
+    /// Get a plural form of the type's name
+    pub fn plural_name(self) -> &'static str {
+        match self {
+            Self::Num => "numbers",
+            Self::Complex => "complexes",
+            Self::Char => "characters",
+            Self::Box => "boxes",
+        }
+    }
+    /// Get the type corresponding to a number returned by `type`
+    pub(crate) fn from_type_num(num: usize) -> Option<Self> {
+        Some(match num {
+            0 => Self::Num,
+            1 => Self::Char,
+            2 => Self::Box,
+            3 => Self::Complex,
+            _ => return None,
+        })
+    }
+}
+
+impl PartialOrd for ElementType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ElementType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let widening = |ty: &Self| match ty {
+            Self::Num => 0,
+            Self::Complex => 1,
+            Self::Char => 2,
+            Self::Box => 3,
+        };
+        widening(self).cmp(&widening(other))
+    }
+}
+
+impl fmt::Display for ElementType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ElementType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "number" | "num" => Ok(Self::Num),
+            "complex" => Ok(Self::Complex),
+            "character" | "char" => Ok(Self::Char),
+            "box" => Ok(Self::Box),
+            _ => Err(format!("unknown element type `{s}`")),
+        }
+    }
+}
+
 /// A combination of [`ExactSizeIterator`] and [`DoubleEndedIterator`]
 pub trait ExactDoubleIterator: ExactSizeIterator + DoubleEndedIterator {}
 impl<T: ExactSizeIterator + DoubleEndedIterator> ExactDoubleIterator for T {}
@@ -89,13 +181,27 @@ impl Value {
     pub(crate) fn builder(capacity: usize) -> ValueBuilder {
         ValueBuilder::with_capacity(capacity)
     }
-    pub(crate) fn type_id(&self) -> u8 {
+    /// Create a value from bytes, decoding them as UTF-8 if possible
+    ///
+    /// Valid UTF-8 becomes a character array.
+    /// Anything else becomes a list of bytes.
+    pub fn from_bytes_auto(bytes: &[u8]) -> Self {
+        match str::from_utf8(bytes) {
+            Ok(s) => s.into(),
+            Err(_) => Self::from_bytes_raw(bytes),
+        }
+    }
+    /// Create a list of bytes, without any decoding
+    pub fn from_bytes_raw(bytes: &[u8]) -> Self {
+        bytes.to_vec().into()
+    }
+    /// Get the type of the value's elements
+    pub fn element_type(&self) -> ElementType {
         match self {
-            Self::Num(_) => f64::TYPE_ID,
-            Self::Byte(_) => u8::TYPE_ID,
-            Self::Complex(_) => Complex::TYPE_ID,
-            Self::Char(_) => char::TYPE_ID,
-            Self::Box(_) => Boxed::TYPE_ID,
+            Self::Num(_) | Self::Byte(_) => ElementType::Num,
+            Self::Complex(_) => ElementType::Complex,
+            Self::Char(_) => ElementType::Char,
+            Self::Box(_) => ElementType::Box,
         }
     }
     /// Get a reference to a possible number array
@@ -119,6 +225,36 @@ impl Value {
             _ => None,
         }
     }
+    /// Get a reference to a possible complex array
+    pub fn as_complex_array(&self) -> Option<&Array<Complex>> {
+        match self {
+            Self::Complex(array) => Some(array),
+            _ => None,
+        }
+    }
+    /// Get the data of a possible complex array as a slice of [`num_complex::Complex64`]
+    ///
+    /// This allows complex arrays to be passed to libraries like `rustfft` without copying.
+    #[cfg(feature = "num-complex")]
+    pub fn as_complex_slice(&self) -> Option<&[num_complex::Complex64]> {
+        (self.as_complex_array()).map(|arr| bytemuck::must_cast_slice(arr.data.as_slice()))
+    }
+    /// Create a complex array from a slice of [`num_complex::Complex64`]
+    ///
+    /// # Panics
+    /// Panics if the number of elements in the shape does not match the length of the data
+    #[cfg(feature = "num-complex")]
+    pub fn from_complex_slice(data: &[num_complex::Complex64], shape: impl Into<Shape>) -> Self {
+        let shape = shape.into();
+        assert_eq!(
+            shape.elements(),
+            data.len(),
+            "shape {shape} does not match data length {}",
+            data.len()
+        );
+        let data: &[Complex] = bytemuck::must_cast_slice(data);
+        Array::new(shape, data).into()
+    }
     /// Get a reference to a possible box array
     pub fn as_box_array(&self) -> Option<&Array<Boxed>> {
         match self {
@@ -167,6 +303,240 @@ impl Value {
             val_as_arr!(self, |array| Box::new(array.into_rows().map(Value::from)))
         }
     }
+    /// Transform each row of the value with a Rust function
+    ///
+    /// Every row's result must have the same shape as the first row's result.
+    /// If the value has no rows, an empty number list is returned.
+    pub fn map_rows<F>(self, f: F, env: &mut Uiua) -> UiuaResult<Value>
+    where
+        F: FnMut(Value, &mut Uiua) -> UiuaResult<Value>,
+    {
+        let row_count = self.row_count();
+        let is_scalar = self.rank() == 0;
+        collect_mapped_rows(row_count, is_scalar, self.into_rows(), f, env)
+    }
+    /// Transform each pair of rows of two values with a Rust function
+    ///
+    /// The values must have the same number of rows unless a fill value is set.
+    /// Every pair's result must have the same shape as the first pair's result.
+    /// If the values have no rows, an empty number list is returned.
+    pub fn zip_rows<F>(mut self, mut other: Value, mut f: F, env: &mut Uiua) -> UiuaResult<Value>
+    where
+        F: FnMut(Value, Value, &mut Uiua) -> UiuaResult<Value>,
+    {
+        if self.row_count() != other.row_count() {
+            fill_value_shapes(&mut self, &mut other, true, env)?;
+        }
+        if self.row_count() != other.row_count() {
+            return Err(env.error(format!(
+                "Cannot zip rows of arrays with different numbers of rows, shapes {} and {}",
+                self.shape(),
+                other.shape()
+            )));
+        }
+        let row_count = self.row_count();
+        let is_scalar = self.rank() == 0 && other.rank() == 0;
+        let rows = self.into_rows().zip(other.into_rows());
+        collect_mapped_rows(row_count, is_scalar, rows, |(a, b), env| f(a, b, env), env)
+    }
+    /// Build a rank 2 array from an iterator of rows
+    ///
+    /// All rows must have the same length.
+    /// If the elements are not scalars, their shape is appended to the array's shape.
+    pub fn from_iter_2d<I, R, T>(rows: I, env: &Uiua) -> UiuaResult<Value>
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = T>,
+        T: Into<Value>,
+    {
+        let mut elems: Vec<Value> = Vec::new();
+        let mut row_count = 0;
+        let mut row_len = None;
+        let mut elem_shape = Shape::default();
+        let mut elem_size = size_of::<u8>();
+        for row in rows {
+            let start = elems.len();
+            for elem in row {
+                let elem = elem.into();
+                if elems.is_empty() {
+                    elem_shape = elem.shape().clone();
+                    elem_size = elem.elem_size();
+                }
+                // Rows are checked as they arrive so that a long row fails early
+                if let Some(first_len) = row_len.filter(|&len| elems.len() - start >= len) {
+                    return Err(env.error(format!(
+                        "Row {row_count} is longer than the first row, \
+                        which has length {first_len}"
+                    )));
+                }
+                validate_size_impl(
+                    elem_size,
+                    [elems.len() + 1]
+                        .into_iter()
+                        .chain(elem_shape.iter().copied()),
+                )
+                .map_err(|e| env.error(e))?;
+                elems.push(elem);
+            }
+            let len = elems.len() - start;
+            match row_len {
+                None => row_len = Some(len),
+                Some(first_len) if len != first_len => {
+                    return Err(env.error(format!(
+                        "Row {row_count} has length {len}, \
+                        but the first row has length {first_len}"
+                    )))
+                }
+                Some(_) => {}
+            }
+            row_count += 1;
+        }
+        let row_len = row_len.unwrap_or(0);
+        let mut val = if elems.is_empty() {
+            Value::default()
+        } else {
+            Value::from_row_values(elems, env)?
+        };
+        let mut shape = Shape::from([row_count, row_len]);
+        shape.extend_from_slice(&elem_shape);
+        *val.shape_mut() = shape;
+        val.validate_shape();
+        Ok(val)
+    }
+    /// Build an array from row values of possibly different types
+    ///
+    /// Number values are promoted to complex numbers if any value is complex.
+    /// If some values are characters and the rest are numbers,
+    /// the numbers are converted to characters if they are all valid codepoints.
+    /// Otherwise, every value is boxed.
+    pub fn type_coerce(vals: Vec<Value>, env: &Uiua) -> UiuaResult<Value> {
+        let has = |ty: ElementType| vals.iter().any(|val| val.element_type() == ty);
+        let (has_num, has_complex, has_char) = (
+            has(ElementType::Num),
+            has(ElementType::Complex),
+            has(ElementType::Char),
+        );
+        let vals = if has(ElementType::Box) || !has_char || !(has_num || has_complex) {
+            vals
+        } else {
+            let chars: Option<Vec<Value>> = (vals.iter())
+                .map(|val| match val {
+                    Value::Char(_) => Some(val.clone()),
+                    Value::Byte(arr) => Some(arr.convert_ref_with(char::from).into()),
+                    Value::Num(arr) => (arr.data.iter())
+                        .map(|&n| {
+                            (n.fract() == 0.0 && n >= 0.0)
+                                .then(|| char::from_u32(n as u32))
+                                .flatten()
+                        })
+                        .collect::<Option<EcoVec<char>>>()
+                        .map(|data| Array::new(arr.shape.clone(), data).into()),
+                    _ => None,
+                })
+                .collect();
+            chars.unwrap_or_else(|| vals.into_iter().map(|val| Boxed(val).into()).collect())
+        };
+        Value::from_row_values(vals, env)
+    }
+    /// Fold the rows of the value into a Rust value
+    ///
+    /// Scalars are treated as a single row.
+    pub fn reduce<A, F>(self, init: A, mut f: F) -> UiuaResult<A>
+    where
+        F: FnMut(A, Value) -> UiuaResult<A>,
+    {
+        let mut acc = init;
+        for row in self.into_rows() {
+            acc = f(acc, row)?;
+        }
+        Ok(acc)
+    }
+    /// Randomly choose `n` distinct rows of the value
+    pub fn sample(&self, n: usize, rng: &mut impl Rng) -> UiuaResult<Value> {
+        let len = self.row_count();
+        if n > len {
+            return Err(UiuaError::new(
+                format!("Cannot sample {n} rows from an array with {len} rows"),
+                Span::Builtin,
+            ));
+        }
+        Ok(self.rows_at(index::sample(rng, len, n)))
+    }
+    /// Randomly choose `n` distinct rows of the value, weighted by a list of numbers
+    ///
+    /// There must be one weight per row.
+    pub fn sample_weighted(
+        &self,
+        weights: &Value,
+        n: usize,
+        rng: &mut impl Rng,
+    ) -> UiuaResult<Value> {
+        let error = |message: String| UiuaError::new(message, Span::Builtin);
+        let weights: Vec<f64> = match weights {
+            Value::Num(arr) if arr.rank() == 1 => arr.data.to_vec(),
+            Value::Byte(arr) if arr.rank() == 1 => arr.data.iter().map(|&b| b as f64).collect(),
+            weights => {
+                return Err(error(format!(
+                    "Weights must be a list of numbers, but they are a rank {} {} array",
+                    weights.rank(),
+                    weights.type_name()
+                )))
+            }
+        };
+        let len = self.row_count();
+        if weights.len() != len {
+            return Err(error(format!(
+                "Cannot sample from an array with {len} rows using {} weights",
+                weights.len()
+            )));
+        }
+        if n > len {
+            return Err(error(format!(
+                "Cannot sample {n} rows from an array with {len} rows"
+            )));
+        }
+        let indices = index::sample_weighted(rng, len, |i| weights[i], n)
+            .map_err(|e| error(format!("Invalid sample weights: {e}")))?;
+        Ok(self.rows_at(indices))
+    }
+    fn rows_at(&self, indices: impl IntoIterator<Item = usize>) -> Value {
+        let rows: Vec<Value> = indices.into_iter().map(|i| self.row(i)).collect();
+        if rows.is_empty() {
+            self.first_dim_zero()
+        } else {
+            Value::from_row_values_infallible(rows)
+        }
+    }
+    /// Check that the value has the given shape
+    pub fn assert_shape(&self, expected: &[usize]) -> UiuaResult {
+        if self.shape().dims() == expected {
+            return Ok(());
+        }
+        Err(UiuaError::new(
+            format!(
+                "Expected {} array of shape {}, but its shape is {}",
+                self.type_name(),
+                Shape::from(expected),
+                self.shape()
+            ),
+            Span::Builtin,
+        ))
+    }
+    /// Check that the value has the given rank
+    pub fn assert_rank(&self, rank: usize) -> UiuaResult {
+        if self.rank() == rank {
+            return Ok(());
+        }
+        Err(UiuaError::new(
+            format!(
+                "Expected rank {rank} {} array, but its rank is {} with shape {}",
+                self.type_name(),
+                self.rank(),
+                self.shape()
+            ),
+            Span::Builtin,
+        ))
+    }
     /// Get an iterator over the elements of the value
     pub fn elements(&self) -> Box<dyn ExactSizeIterator<Item = Self> + '_> {
         val_as_arr!(self, |array| Box::new(
@@ -181,23 +551,11 @@ impl Value {
     }
     /// Get the value's type name
     pub fn type_name(&self) -> &'static str {
-        match self {
-            Self::Num(_) => "number",
-            Self::Byte(_) => "number",
-            Self::Complex(_) => "complex",
-            Self::Char(_) => "character",
-            Self::Box(_) => "box",
-        }
+        self.element_type().name()
     }
     /// Get a plural form of the value's type name
     pub fn type_name_plural(&self) -> &'static str {
-        match self {
-            Self::Num(_) => "numbers",
-            Self::Byte(_) => "numbers",
-            Self::Complex(_) => "complexes",
-            Self::Char(_) => "characters",
-            Self::Box(_) => "boxes",
-        }
+        self.element_type().plural_name()
     }
     /// Get the number of rows
     pub fn row_count(&self) -> usize {
@@ -319,6 +677,44 @@ impl Value {
     }
 }
 
+fn collect_mapped_rows<R>(
+    row_count: usize,
+    is_scalar: bool,
+    mut rows: impl Iterator<Item = R>,
+    mut f: impl FnMut(R, &mut Uiua) -> UiuaResult<Value>,
+    env: &mut Uiua,
+) -> UiuaResult<Value> {
+    let Some(first) = rows.next() else {
+        return Ok(Value::default());
+    };
+    let first = f(first, env)?;
+    let row_shape = first.shape().clone();
+    validate_size_impl(
+        first.elem_size(),
+        once(row_count).chain(row_shape.iter().copied()),
+    )
+    .map_err(|e| env.error(e))?;
+    let mut results = Vec::with_capacity(row_count);
+    results.push(first);
+    for (i, row) in rows.enumerate() {
+        let res = f(row, env)?;
+        if *res.shape() != row_shape {
+            return Err(env.error(format!(
+                "Row {} was mapped to shape {}, \
+                but the first row was mapped to shape {row_shape}",
+                i + 1,
+                res.shape()
+            )));
+        }
+        results.push(res);
+    }
+    let mut val = Value::from_row_values(results, env)?;
+    if is_scalar {
+        val.undo_fix();
+    }
+    Ok(val)
+}
+
 #[repr(C)]
 struct Repr {
     discriminant: u8,
@@ -2161,7 +2557,7 @@ impl PartialOrd for Value {
 
 impl Ord for Value {
     fn cmp(&self, other: &Self) -> Ordering {
-        let type_order = self.type_id().cmp(&other.type_id());
+        let type_order = self.element_type().cmp(&other.element_type());
         if type_order != Ordering::Equal {
             return type_order;
         }
@@ -2286,3 +2682,65 @@ impl ValueBuilder {
         self.value.unwrap_or_default()
     }
 }
+
+/// A rank 2 array with named columns
+///
+/// Created with [`Value::as_table`]
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// The names of the columns
+    pub columns: Vec<String>,
+    /// The rank 2 data
+    pub data: Value,
+}
+
+impl Value {
+    /// Treat a rank 2 array as a table with the given column names
+    ///
+    /// The column names may be a list of boxed strings or a rank 2 character array.
+    pub fn as_table(self, columns: &Value, env: &Uiua) -> UiuaResult<Table> {
+        if self.rank() != 2 {
+            return Err(env.error(format!(
+                "Table data must be rank 2, but its shape is {}",
+                self.shape()
+            )));
+        }
+        let columns = match columns {
+            Value::Char(arr) if arr.rank() == 2 => {
+                (arr.row_slices()).map(|row| row.iter().collect()).collect()
+            }
+            Value::Box(arr) if arr.rank() == 1 => (arr.data.iter())
+                .map(|Boxed(name)| name.as_string(env, "Column names must be strings"))
+                .collect::<UiuaResult<Vec<_>>>()?,
+            _ => return Err(env.error("Column names must be a list of strings")),
+        };
+        if columns.len() != self.shape()[1] {
+            return Err(env.error(format!(
+                "Table has {} column names but {} columns",
+                columns.len(),
+                self.shape()[1]
+            )));
+        }
+        Ok(Table {
+            columns,
+            data: self,
+        })
+    }
+}
+
+impl Table {
+    /// Get the column with the given name
+    pub fn get_column(&self, name: &str) -> UiuaResult<Value> {
+        let index = (self.columns.iter().position(|col| col == name)).ok_or_else(|| {
+            UiuaError::new(format!("Table has no column `{name}`"), Span::Builtin)
+        })?;
+        let mut data = self.data.clone();
+        data.transpose();
+        Ok(data.row(index))
+    }
+    /// Split the table into its data and a list of boxed column names
+    pub fn to_value(&self) -> (Value, Value) {
+        let columns = Array::<Boxed>::from_iter(self.columns.iter().map(String::as_str));
+        (self.data.clone(), columns.into())
+    }
+}
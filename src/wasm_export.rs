@@ -0,0 +1,248 @@
+//! Export Uiua assemblies as standalone WASM modules
+//!
+//! An exported module is the prebuilt Uiua WASM runtime with an assembly embedded in custom sections.
+//! The runtime is this crate built for `wasm32-unknown-unknown` with the `wasm_export` feature
+//! and processed with `wasm-bindgen`.
+//!
+//! Hosts read the [`ASSEMBLY_SECTION`] and [`EXPORTS_SECTION`] custom sections
+//! (for example with `WebAssembly.Module.customSections`) and pass them to [`UiuaModule::new`].
+//! [`UiuaModule::exports`] then returns an object with one function per exported binding.
+
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array as JsArray, Float64Array, Function as JsFunction, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::{Assembly, Boxed, Function, SafeSys, Uiua, Value};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{BindingKind, Span, UiuaError, UiuaResult};
+
+/// The name of the custom section that contains the assembly
+pub const ASSEMBLY_SECTION: &str = "uiua_asm";
+/// The name of the custom section that contains the newline-separated exported binding names
+pub const EXPORTS_SECTION: &str = "uiua_exports";
+/// The environment variable that contains the path to the prebuilt runtime
+pub const RUNTIME_VAR: &str = "UIUA_WASM_RUNTIME";
+
+/// Generate a WASM module that exports the given function bindings of an assembly
+///
+/// The prebuilt runtime is read from the path in the [`RUNTIME_VAR`] environment variable.
+/// If it is not set when the program runs, the value it had at build time is used.
+///
+/// Returns an error if the runtime cannot be read
+/// or if any of the names is not a function binding.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn to_wasm_module(asm: Assembly, export_names: &[&str]) -> UiuaResult<Vec<u8>> {
+    let path = (std::env::var(RUNTIME_VAR).ok())
+        .or_else(|| option_env!("UIUA_WASM_RUNTIME").map(Into::into))
+        .ok_or_else(|| {
+            UiuaError::new(
+                format!("The {RUNTIME_VAR} environment variable must be set to export WASM"),
+                Span::Builtin,
+            )
+        })?;
+    let runtime = std::fs::read(&path).map_err(|e| {
+        UiuaError::new(
+            format!("Failed to read WASM runtime {path}: {e}"),
+            Span::Builtin,
+        )
+    })?;
+    to_wasm_module_with_runtime(asm, export_names, &runtime)
+}
+
+/// Generate a WASM module from a given prebuilt runtime
+///
+/// See [`to_wasm_module`]
+#[cfg(not(target_arch = "wasm32"))]
+pub fn to_wasm_module_with_runtime(
+    asm: Assembly,
+    export_names: &[&str],
+    runtime: &[u8],
+) -> UiuaResult<Vec<u8>> {
+    if !runtime.starts_with(b"\0asm\x01\0\0\0") {
+        return Err(UiuaError::new(
+            "The WASM runtime is not a WASM module",
+            Span::Builtin,
+        ));
+    }
+    for &name in export_names {
+        let is_func = asm.bindings.iter().any(|binding| {
+            matches!(binding.kind, BindingKind::Func(_))
+                && binding.span.as_str(&asm.inputs, |s| s == name)
+        });
+        if !is_func {
+            return Err(UiuaError::new(
+                format!("`{name}` is not a function binding"),
+                Span::Builtin,
+            ));
+        }
+    }
+    let mut module = runtime.to_vec();
+    write_custom_section(&mut module, ASSEMBLY_SECTION, asm.to_uasm().as_bytes());
+    write_custom_section(
+        &mut module,
+        EXPORTS_SECTION,
+        export_names.join("\n").as_bytes(),
+    );
+    Ok(module)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_custom_section(module: &mut Vec<u8>, name: &str, data: &[u8]) {
+    fn leb128(bytes: &mut Vec<u8>, mut n: usize) {
+        loop {
+            let byte = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                bytes.push(byte);
+                break;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+    let mut payload = Vec::with_capacity(name.len() + data.len() + 5);
+    leb128(&mut payload, name.len());
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+    module.push(0);
+    leb128(module, payload.len());
+    module.extend(payload);
+}
+
+/// A Uiua assembly loaded in the WASM runtime
+#[wasm_bindgen]
+pub struct UiuaModule {
+    env: Rc<RefCell<Uiua>>,
+    exports: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl UiuaModule {
+    /// Load an assembly and run its top-level code
+    #[wasm_bindgen(constructor)]
+    pub fn new(uasm: &str, exports: &str) -> Result<UiuaModule, JsValue> {
+        let asm = Assembly::from_uasm(uasm).map_err(|e| JsValue::from_str(&e))?;
+        let mut env = Uiua::with_backend(SafeSys::default());
+        env.run_asm(asm)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        env.take_stack();
+        let exports = exports.lines().map(Into::into).collect();
+        Ok(UiuaModule {
+            env: Rc::new(RefCell::new(env)),
+            exports,
+        })
+    }
+    /// Get an object with a function for each exported binding
+    ///
+    /// Each function takes the binding's arguments as normal JS arguments.
+    /// See [`UiuaModule::call`] for how values are converted.
+    pub fn exports(&self) -> Result<Object, JsValue> {
+        let object = Object::new();
+        // Turns a function taking an array of arguments into a variadic function
+        let variadic = JsFunction::new_with_args("f", "return (...args) => f(args)");
+        for name in &self.exports {
+            let f = self.function(name)?;
+            let env = self.env.clone();
+            let name_clone = name.clone();
+            let call = Closure::<dyn Fn(Vec<JsValue>) -> Result<JsValue, JsValue>>::new(
+                move |args: Vec<JsValue>| {
+                    call_function(&mut env.borrow_mut(), &name_clone, &f, args)
+                },
+            );
+            let func = variadic.call1(&JsValue::NULL, &call.into_js_value())?;
+            Reflect::set(&object, &JsValue::from_str(name), &func)?;
+        }
+        Ok(object)
+    }
+    /// Call an exported function
+    ///
+    /// The first argument is on top of the stack.
+    /// Numbers, strings, `Float64Array`s, and nested arrays of those are accepted.
+    /// If the function has one output, it is returned directly.
+    /// Otherwise, the outputs are returned in an array with the top of the stack first.
+    pub fn call(&mut self, name: &str, args: Vec<JsValue>) -> Result<JsValue, JsValue> {
+        let f = self.function(name)?;
+        call_function(&mut self.env.borrow_mut(), name, &f, args)
+    }
+}
+
+impl UiuaModule {
+    fn function(&self, name: &str) -> Result<Function, JsValue> {
+        (self.exports.iter().any(|export| export == name))
+            .then(|| self.env.borrow().bound_functions().remove(name))
+            .flatten()
+            .ok_or_else(|| JsValue::from_str(&format!("`{name}` is not exported")))
+    }
+}
+
+fn call_function(
+    env: &mut Uiua,
+    name: &str,
+    f: &Function,
+    args: Vec<JsValue>,
+) -> Result<JsValue, JsValue> {
+    if args.len() != f.sig.args {
+        return Err(JsValue::from_str(&format!(
+            "`{name}` expects {} arguments, but {} were given",
+            f.sig.args,
+            args.len()
+        )));
+    }
+    for arg in args.into_iter().rev() {
+        env.push(js_to_value(arg)?);
+    }
+    (env.call(f)).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let mut outputs = env.take_stack();
+    outputs.reverse();
+    Ok(if outputs.len() == 1 {
+        value_to_js(outputs.pop().unwrap())
+    } else {
+        outputs
+            .into_iter()
+            .map(value_to_js)
+            .collect::<JsArray>()
+            .into()
+    })
+}
+
+fn js_to_value(val: JsValue) -> Result<Value, JsValue> {
+    if let Some(n) = val.as_f64() {
+        Ok(n.into())
+    } else if let Some(b) = val.as_bool() {
+        Ok(b.into())
+    } else if let Some(s) = val.as_string() {
+        Ok(s.into())
+    } else if let Some(arr) = val.dyn_ref::<Float64Array>() {
+        Ok(arr.to_vec().into_iter().collect())
+    } else if let Some(arr) = val.dyn_ref::<JsArray>() {
+        let rows = (arr.iter())
+            .map(js_to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(
+            Value::from_row_values(rows.clone(), &()).unwrap_or_else(|_| {
+                let boxes = rows.into_iter().map(|row| Boxed(row).into());
+                Value::from_row_values_infallible(boxes.collect::<Vec<Value>>())
+            }),
+        )
+    } else {
+        Err(JsValue::from_str("Unsupported argument type"))
+    }
+}
+
+fn value_to_js(val: Value) -> JsValue {
+    match val {
+        Value::Char(arr) if arr.rank() == 1 => arr.data.iter().collect::<String>().into(),
+        Value::Box(arr) if arr.rank() == 0 => value_to_js(arr.data[0].0.clone()),
+        val if val.rank() == 0 => match val {
+            Value::Num(arr) => arr.data[0].into(),
+            Value::Byte(arr) => arr.data[0].into(),
+            Value::Char(arr) => arr.data[0].to_string().into(),
+            Value::Complex(arr) => {
+                JsArray::of2(&arr.data[0].re.into(), &arr.data[0].im.into()).into()
+            }
+            Value::Box(_) => unreachable!(),
+        },
+        Value::Num(arr) if arr.rank() == 1 => Float64Array::from(&arr.data[..]).into(),
+        val => val.into_rows().map(value_to_js).collect::<JsArray>().into(),
+    }
+}